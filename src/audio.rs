@@ -0,0 +1,67 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+
+use crate::ball::{BallBouncedEvent, Surface};
+use crate::blocks::BlockDestroyedEvent;
+use crate::settings::Settings;
+use crate::state::{BallLostEvent, GameWonEvent};
+
+#[derive(Resource)]
+pub struct GameSounds {
+    pub paddle: Handle<AudioSource>, // Ball hits the paddle
+    pub wall: Handle<AudioSource>, // Ball hits a wall or the ceiling
+    pub block_break: Handle<AudioSource>, // A block is destroyed
+    pub lose: Handle<AudioSource>, // The last ball fell off the bottom
+    pub win: Handle<AudioSource>, // The last level was cleared
+}
+
+// Load every clip once so playing a sound doesn't reload it
+pub fn load_sounds(mut commands: Commands,
+                   asset_server: Res<AssetServer>) {
+
+    commands.insert_resource(GameSounds {
+        paddle: asset_server.load("sounds/bounce.ogg"),
+        wall: asset_server.load("sounds/wall.ogg"),
+        block_break: asset_server.load("sounds/break.ogg"),
+        lose: asset_server.load("sounds/lose.ogg"),
+        win: asset_server.load("sounds/win.ogg"),
+    });
+}
+
+// Play a sound for everything that happened this frame, only runs while playing so nothing plays while paused
+pub fn play_sounds(mut commands: Commands,
+                   mut bounces: EventReader<BallBouncedEvent>,
+                   mut destroyed: EventReader<BlockDestroyedEvent>,
+                   mut lost: EventReader<BallLostEvent>,
+                   mut won: EventReader<GameWonEvent>,
+                   sounds: Res<GameSounds>,
+                   settings: Res<Settings>) {
+
+    let (mut paddle, mut wall) = (false, false);
+    for event in bounces.read() {
+        match event.surface {
+            Surface::Paddle => paddle = true,
+            Surface::Wall | Surface::Ceiling => wall = true,
+        }
+    }
+
+    // At most one of each sound per frame however many hits there were
+    let clips = [
+        (paddle, &sounds.paddle),
+        (wall, &sounds.wall),
+        (destroyed.read().count() > 0, &sounds.block_break),
+        (lost.read().count() > 0, &sounds.lose),
+        (won.read().count() > 0, &sounds.win),
+    ];
+    for (_, sound) in clips.into_iter().filter(|(happened, _)| *happened) {
+        play_sound(&mut commands, sound, settings.effective_sfx_volume());
+    }
+}
+
+// Play a clip once, the entity despawns itself when the clip ends
+fn play_sound(commands: &mut Commands, sound: &Handle<AudioSource>, volume: f32) {
+    commands.spawn((
+        AudioPlayer::new(sound.clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume.clamp(0.0, 1.0))),
+    ));
+}
@@ -0,0 +1,396 @@
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use rand::Rng;
+
+use crate::collision::sweep_hit;
+use crate::config::GameConfig;
+use crate::paddle::{PaddleVelocity, PaddleWidth, Player};
+use crate::powerup::StickyPaddle;
+use crate::settings::KeyBindings;
+use crate::state::{Difficulty, RespawnTimer};
+use crate::ui::Combo;
+use crate::*;
+
+#[derive(Component)]
+#[require(Velocity, PreviousPosition, BallSpeed, StallWatch, BallTrail, Spin)]
+pub struct Ball;
+
+#[derive(Component, Default)]
+pub struct StallWatch {
+    pub band: i32, // Horizontal band of the screen the ball was last seen in
+    pub time: f32, // Seconds it has stayed in that band
+}
+
+// How fast the ball goes, Velocity only gives its direction. Bounces turn the ball,
+// only speed ups and slow downs change this
+#[derive(Component)]
+pub struct BallSpeed(pub f32);
+
+impl Default for BallSpeed {
+    fn default() -> Self {
+        BallSpeed(BALL_START_SPEED)
+    }
+}
+
+#[derive(Component, Default)]
+pub struct BallTrail(pub VecDeque<Vec2>); // The ball's last TRAIL_LENGTH positions, newest first
+
+#[derive(Component, Default)]
+pub struct Spin(pub f32); // From -1.0 to 1.0, curves the ball towards positive x when positive and wears off over time
+
+#[derive(Component, Default)]
+pub struct PreviousPosition(pub Vec2); // Where the ball was before the last fixed step's movement
+
+#[derive(Component)]
+pub struct BallVisual; // The ball's mesh, a child drawn between the last two fixed step positions
+
+#[derive(Component)]
+pub struct StuckToPaddle {
+    pub offset: f32, // Distance from the paddle's center, kept while it rides along
+}
+
+#[derive(Resource)]
+pub struct LaunchCountdown(pub Timer); // The served ball launches by itself once this runs out
+
+#[derive(Resource, Default)]
+pub struct LaunchAim(pub f32); // Radians from straight up the served ball will launch at, positive is to the right
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Surface {
+    Paddle,
+    Wall, // Left or right side of the window
+    Ceiling,
+}
+
+#[derive(Event)]
+pub struct BallBouncedEvent {
+    pub surface: Surface, // What the ball bounced off
+}
+
+#[derive(Resource)]
+pub struct BallAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<ColorMaterial>,
+}
+
+// How far the ball is from its starting speed to MAX_BALL_SPEED, from 0.0 to 1.0
+pub fn speed_fraction(speed: f32, start_speed: f32) -> f32 {
+    ((speed - start_speed) / (MAX_BALL_SPEED - start_speed).max(1.0)).clamp(0.0, 1.0)
+}
+
+// Shade each ball from green to red as it speeds up
+pub fn ball_color(balls: Query<(&BallSpeed, &Children), With<Ball>>,
+                  mut visuals: Query<&mut MeshMaterial2d<ColorMaterial>, With<BallVisual>>,
+                  mut materials: ResMut<Assets<ColorMaterial>>,
+                  assets: Option<Res<BallAssets>>,
+                  config: Res<GameConfig>,
+                  difficulty: Res<Difficulty>) {
+
+    let Some(assets) = assets else {
+        return;
+    };
+    for (speed, children) in &balls {
+        let color = BALL_SLOW_COLOR.mix(&BALL_FAST_COLOR, speed_fraction(speed.0, difficulty.ball_speed(&config)));
+        for child in children.iter() {
+            let Ok(mut material) = visuals.get_mut(child) else {
+                continue;
+            };
+            if material.0 == assets.material {
+                // Balls start on the shared material, give this one its own the first time so they can differ
+                material.0 = materials.add(color);
+            } else if materials.get(&material.0).is_some_and(|existing| existing.color != color) {
+                // get_mut marks the asset changed, so only call it when the color moved
+                if let Some(existing) = materials.get_mut(&material.0) {
+                    existing.color = color;
+                }
+            }
+        }
+    }
+}
+
+// Spawn the ball resting on the middle of the paddle and count down to launching it,
+// used at the start of every life and level
+pub fn spawn_ball(commands: &mut Commands, assets: &BallAssets, dimensions: &GameDimensions, config: &GameConfig, speed: f32) {
+    let position = Vec2::new(0.0, dimensions.player_y() + PLAYER_WIDTH / 2.0 + config.ball_size / 2.0);
+    let ball = spawn_ball_at(commands, assets, position, Vec2::ZERO);
+    commands.entity(ball).insert((StuckToPaddle { offset: 0.0 }, BallSpeed(speed)));
+    commands.insert_resource(LaunchCountdown(Timer::from_seconds(LAUNCH_COUNTDOWN, TimerMode::Once)));
+    commands.insert_resource(LaunchAim::default());
+}
+
+// Spawn a ball in play, its speed is the length of the velocity
+pub fn spawn_ball_at(commands: &mut Commands, assets: &BallAssets, position: Vec2, velocity: Vec2) -> Entity {
+    commands.spawn((
+        Ball,
+        DespawnOnGameOver, // This component will be used to despawn the ball on game over
+        Transform::from_translation(position.extend(0.0)),
+        PreviousPosition(position),
+        Velocity(velocity),
+        BallSpeed(velocity.length()),
+        Visibility::default(),
+        children![(
+            BallVisual,
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(assets.material.clone()),
+        )],
+    )).id()
+}
+
+// Keep stuck balls sitting on the paddle wherever it moves
+#[allow(clippy::type_complexity)]
+pub fn follow_paddle(mut balls: Query<(&mut Transform, &mut PreviousPosition, &mut BallTrail, &StuckToPaddle), Without<Player>>,
+                     player: Query<&Transform, With<Player>>,
+                     config: Res<GameConfig>) {
+
+    let Ok(player_tf) = player.single() else {
+        return;
+    };
+    for (mut ball_tf, mut previous, mut trail, stuck) in balls.iter_mut() {
+        trail.0.clear(); // A resting ball leaves no trail
+        ball_tf.translation.x = player_tf.translation.x + stuck.offset;
+        ball_tf.translation.y = player_tf.translation.y + PLAYER_WIDTH / 2.0 + config.ball_size / 2.0;
+        previous.0 = ball_tf.translation.truncate(); // Riding along isn't movement collisions should sweep
+    }
+}
+
+// Turn the served ball's aim with the movement keys or the d-pad
+pub fn aim_launch(mut aim: ResMut<LaunchAim>,
+                  time: Res<Time>,
+                  keyboard_input: Res<ButtonInput<KeyCode>>,
+                  bindings: Res<KeyBindings>,
+                  gamepads: Query<&Gamepad>) {
+
+    if keyboard_input.any_pressed([bindings.move_left, KeyCode::ArrowLeft]) || gamepads.iter().any(|gamepad| gamepad.pressed(GamepadButton::DPadLeft)) {
+        aim.0 -= AIM_SPEED * time.delta_secs();
+    }
+    if keyboard_input.any_pressed([bindings.move_right, KeyCode::ArrowRight]) || gamepads.iter().any(|gamepad| gamepad.pressed(GamepadButton::DPadRight)) {
+        aim.0 += AIM_SPEED * time.delta_secs();
+    }
+    aim.0 = aim.0.clamp(-MAX_LAUNCH_ANGLE, MAX_LAUNCH_ANGLE); // Never straight into a wall
+}
+
+// Point the served ball's aim at the cursor
+pub fn aim_at_cursor(mut aim: ResMut<LaunchAim>,
+                     balls: Query<&Transform, With<StuckToPaddle>>,
+                     window: Query<&Window, With<PrimaryWindow>>,
+                     camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>) {
+
+    let (Ok(window), Ok((camera, camera_tf)), Some(ball)) = (window.single(), camera.single(), balls.iter().next()) else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return; // Cursor is outside the window
+    };
+    let Ok(cursor) = camera.viewport_to_world_2d(camera_tf, cursor) else {
+        return;
+    };
+
+    let to_cursor = cursor - ball.translation.truncate();
+    aim.0 = to_cursor.x.atan2(to_cursor.y.max(0.0)).clamp(-MAX_LAUNCH_ANGLE, MAX_LAUNCH_ANGLE);
+}
+
+// Launch every stuck ball with the serve key, Space or the gamepad's South button. A served ball goes where it's aimed,
+// by itself once the countdown ends, and a caught one is angled by where it sits on the paddle
+#[allow(clippy::too_many_arguments)]
+pub fn launch_ball(mut commands: Commands,
+                   mut balls: Query<(Entity, &mut Velocity, &BallSpeed, &StuckToPaddle)>,
+                   player: Query<&PaddleWidth, With<Player>>,
+                   countdown: Option<ResMut<LaunchCountdown>>,
+                   aim: Option<Res<LaunchAim>>,
+                   time: Res<Time>,
+                   keyboard_input: Res<ButtonInput<KeyCode>>,
+                   bindings: Res<KeyBindings>,
+                   gamepads: Query<&Gamepad>) {
+
+    let pressed = keyboard_input.any_just_pressed([bindings.serve, KeyCode::Space])
+        || gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+    let finished = countdown.is_some_and(|mut countdown| countdown.0.tick(time.delta()).finished());
+    if !pressed && !finished {
+        return;
+    }
+    let Ok(width) = player.single() else {
+        return;
+    };
+    commands.remove_resource::<LaunchCountdown>();
+    commands.remove_resource::<LaunchAim>();
+
+    for (entity, mut vel, speed, stuck) in balls.iter_mut() {
+        vel.0 = match &aim {
+            Some(aim) => Vec2::new(aim.0.sin(), aim.0.cos()) * speed.0,
+            None => paddle_bounce(stuck.offset / (width.0 / 2.0), speed.0),
+        };
+        commands.entity(entity).remove::<StuckToPaddle>();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn ball_movement(mut ball: Query<(&mut Transform, &mut PreviousPosition, &mut Velocity, &mut BallSpeed, &mut BallTrail, &mut Spin), Without<StuckToPaddle>>,
+                     mut bounce_events: EventWriter<BallBouncedEvent>,
+                     dimensions: Res<GameDimensions>,
+                     config: Res<GameConfig>,
+                     time: Res<Time>){
+
+    for (mut transform, mut previous, mut vel, mut speed, mut trail, mut spin) in ball.iter_mut() {
+        previous.0 = transform.translation.truncate();
+        trail.0.push_front(previous.0);
+        trail.0.truncate(TRAIL_LENGTH);
+        // Spin only turns the ball, the speed below stays the same. It wears off, so it can't curl it round in circles
+        vel.0.x += spin.0.clamp(-1.0, 1.0) * SPIN_CURVE * time.delta_secs();
+        spin.0 *= SPIN_DECAY.powf(time.delta_secs());
+        vel.0 = clamp_angle(vel.0.normalize_or_zero() * speed.0); // Same direction, at the ball's current speed
+
+        // Update position
+        transform.translation.x += vel.0.x * time.delta_secs();
+        transform.translation.y += vel.0.y * time.delta_secs();
+
+        // Bounce off walls, moving the ball back inside. Only flip when moving into the wall,
+        // a ball that's already heading away must not be turned back
+        let max_x = dimensions.width / 2.0 - config.ball_size / 2.0;
+        let max_y = dimensions.height / 2.0 - config.ball_size / 2.0;
+        // Hitting a wall or the ceiling head-on, rather than glancing off it, stops the spin
+        if (transform.translation.x < -max_x && vel.0.x < 0.0) || (transform.translation.x > max_x && vel.0.x > 0.0) {
+            if vel.0.x.abs() > vel.0.y.abs() {
+                spin.0 = 0.0;
+            }
+            vel.0.x = -vel.0.x; // Invert the x velocity
+            bounce_events.write(BallBouncedEvent { surface: Surface::Wall });
+        }
+        if transform.translation.y > max_y && vel.0.y > 0.0 {
+            if vel.0.y.abs() > vel.0.x.abs() {
+                spin.0 = 0.0;
+            }
+            vel.0.y = -vel.0.y; // Invert the y velocity
+            bounce_events.write(BallBouncedEvent { surface: Surface::Ceiling });
+        }
+        transform.translation.x = transform.translation.x.clamp(-max_x, max_x);
+        transform.translation.y = transform.translation.y.min(max_y);
+
+        // Slowly speed up the longer the ball is in play, virtual time stands still while paused
+        speed.0 = (speed.0 + BALL_TIME_SPEEDUP * time.delta_secs()).min(MAX_BALL_SPEED);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn ball_collision(mut commands: Commands,
+                      mut balls: Query<(Entity, &mut Transform, &PreviousPosition, &mut Velocity, &mut BallSpeed, &mut Spin), (With<Ball>, Without<StuckToPaddle>)>,
+                      player: Query<(&Transform, &PaddleWidth, &PaddleVelocity, Has<StickyPaddle>), (With<Player>, Without<Ball>)>,
+                      mut combo: ResMut<Combo>,
+                      config: Res<GameConfig>,
+                      mut bounce_events: EventWriter<BallBouncedEvent>) {
+
+    if let Ok((player_tf, width, paddle_velocity, sticky)) = player.single() {
+
+        for (ball_entity, mut ball_tf, previous, mut vel, mut speed, mut spin) in balls.iter_mut() {
+
+            // Check the whole path so a fast ball can't skip over the paddle
+            if let Some((t, _)) = sweep_hit(previous.0,
+                                            ball_tf.translation.truncate(),
+                                            Vec2::splat(config.ball_size / 2.0),
+                                            player_tf.translation.truncate(),
+                                            Vec2::new(width.0, PLAYER_WIDTH) / 2.0) {
+
+                if vel.0.y > 0.0 {
+                    continue; // Already bouncing away from the paddle
+                }
+
+                // Move back to where the ball touched the paddle so it bounces from there
+                let contact = previous.0.lerp(ball_tf.translation.truncate(), t);
+                ball_tf.translation.x = contact.x;
+                ball_tf.translation.y = contact.y;
+
+                bounce_events.write(BallBouncedEvent { surface: Surface::Paddle });
+                combo.0 = 0; // The rally is over
+                if sticky {
+                    // Catch the ball, it's launched again from here
+                    commands.entity(ball_entity).insert(StuckToPaddle {
+                        offset: ball_tf.translation.x - player_tf.translation.x,
+                    });
+                    vel.0 = Vec2::ZERO;
+                    spin.0 = 0.0;
+                    continue;
+                }
+
+                speed.0 = (speed.0 * PADDLE_HIT_SPEEDUP).min(MAX_BALL_SPEED);
+                let offset = (ball_tf.translation.x - player_tf.translation.x) / (width.0 / 2.0);
+                vel.0 = paddle_bounce(offset, speed.0); // Angle depends on where the paddle was hit
+                // A paddle moving at full speed gives full spin, the mouse can't give any more than that
+                spin.0 = (paddle_velocity.x / config.paddle_speed).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+// Draw each ball part way between its last two fixed step positions, by how far time has got towards the next step.
+// The ball's own Transform stays where the physics put it
+pub fn interpolate_balls(balls: Query<(&Transform, &PreviousPosition), Without<BallVisual>>,
+                         mut visuals: Query<(&mut Transform, &ChildOf), With<BallVisual>>,
+                         fixed_time: Res<Time<Fixed>>) {
+
+    let alpha = fixed_time.overstep_fraction();
+    for (mut visual_tf, child_of) in visuals.iter_mut() {
+        if let Ok((ball_tf, previous)) = balls.get(child_of.parent()) {
+            let offset = (previous.0 - ball_tf.translation.truncate()) * (1.0 - alpha);
+            visual_tf.translation = offset.extend(0.0);
+        }
+    }
+}
+
+// Keep the ball from travelling too close to either axis so it can't get stuck going
+// straight up and down an empty column or side to side between the walls. The speed is unchanged
+pub fn clamp_angle(velocity: Vec2) -> Vec2 {
+    let speed = velocity.length();
+    if speed == 0.0 {
+        return velocity;
+    }
+    let min = speed * MIN_AXIS_SPEED;
+    let max = speed * (1.0 - MIN_AXIS_SPEED * MIN_AXIS_SPEED).sqrt(); // Leaves at least `min` for y
+    let x = velocity.x.abs().clamp(min, max).copysign(velocity.x);
+    let y = (speed * speed - x * x).sqrt().copysign(velocity.y);
+    Vec2::new(x, y)
+}
+
+// Give a ball that hasn't left its horizontal band for a while a small random vertical kick
+pub fn ball_watchdog(mut balls: Query<(&Transform, &mut Velocity, &mut StallWatch), Without<StuckToPaddle>>,
+                     mut rng: ResMut<GameRng>,
+                     time: Res<Time>) {
+
+    for (transform, mut vel, mut watch) in balls.iter_mut() {
+        let band = (transform.translation.y / STALL_BAND_HEIGHT).floor() as i32;
+        if band != watch.band {
+            watch.band = band;
+            watch.time = 0.0;
+            continue;
+        }
+
+        watch.time += time.delta_secs();
+        if watch.time > STALL_TIMEOUT {
+            let kick = rng.rng.gen_range(-0.5..0.5) * STALL_KICK; // ball_movement puts the speed back
+            vel.0 = Vec2::from_angle(kick).rotate(vel.0);
+            watch.time = 0.0;
+        }
+    }
+}
+
+// Outgoing velocity for a paddle hit, offset is -1.0 (left edge) to 1.0 (right edge)
+pub fn paddle_bounce(offset: f32, speed: f32) -> Vec2 {
+    let x = offset.clamp(-1.0, 1.0) * BALL_MAX_X_SPEED.min(speed * 0.9); // Always keep some upward speed
+    let y = (speed * speed - x * x).sqrt(); // Keep the overall speed unchanged
+    Vec2::new(x, y)
+}
+
+// Replace a lost ball once the respawn delay has passed
+pub fn respawn_ball(mut commands: Commands,
+                    time: Res<Time>, // Virtual time, so the delay doesn't tick while paused
+                    ball_assets: Option<Res<BallAssets>>, // Only there once a game has started, not in the menu
+                    dimensions: Res<GameDimensions>,
+                    config: Res<GameConfig>,
+                    difficulty: Res<Difficulty>,
+                    timer: Option<ResMut<RespawnTimer>>) {
+
+    if let (Some(mut timer), Some(ball_assets)) = (timer, ball_assets)
+        && timer.0.tick(time.delta()).finished() {
+        commands.remove_resource::<RespawnTimer>();
+        spawn_ball(&mut commands, &ball_assets, &dimensions, &config, difficulty.ball_speed(&config));
+    }
+}
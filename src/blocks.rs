@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::ball::{Ball, BallSpeed, PreviousPosition, StuckToPaddle};
+use crate::collision::{reflect, sweep_hit};
+use crate::config::GameConfig;
+use crate::effects::ScreenShake;
+use crate::layout::{Cell, LevelLayout};
+use crate::levels::{CurrentLevel, LevelData};
+use crate::state::Difficulty;
+use crate::*;
+
+#[derive(Component)]
+#[require(BlockRow)]
+pub struct Block;
+
+#[derive(Component, Default)]
+pub struct BlockRow(pub usize); // Row of the layout the block is in, picks its color
+
+#[derive(Component)]
+pub struct Indestructible; // Balls bounce off but never break it, the level is clear without it
+
+#[derive(Component)]
+pub struct Explosive; // Breaking it breaks every block within the blast radius too
+
+#[derive(Component)]
+pub struct Moving {
+    pub speed: f32, // Pixels per second, negative while heading left
+    pub range: f32, // Furthest it goes either side of origin_x
+    pub origin_x: f32, // Where it was spawned
+    pub step: f32, // How far it moved in the last fixed step, collisions allow for it
+}
+
+#[derive(Component)]
+pub struct Durability(pub u32); // Hits left before a block breaks
+
+#[derive(Component)]
+pub struct PointValue(pub u32); // Score awarded when a block breaks
+
+#[derive(Event)]
+pub struct BlockDestroyedEvent {
+    pub entity: Entity,
+    pub position: Vec3, // Where the block was
+    pub value: u32, // Points the block was worth
+    pub material: Handle<ColorMaterial>, // What the block looked like when it broke
+}
+
+#[derive(Event)]
+pub struct ExplosionEvent {
+    pub position: Vec3, // Center of the blast
+}
+
+#[derive(Resource)]
+pub struct BlockMaterials(pub Vec<Vec<Handle<ColorMaterial>>>); // Per row, one material per remaining hit, index 0 is 1 hit left
+
+// Classic rainbow, repeating for layouts with more rows
+const ROW_COLORS: [(f32, f32, f32); 6] = [
+    (1.0, 0.2, 0.2),
+    (1.0, 0.6, 0.1),
+    (1.0, 0.9, 0.2),
+    (0.2, 0.9, 0.3),
+    (0.2, 0.5, 1.0),
+    (0.6, 0.3, 1.0),
+];
+
+// Spawn the blocks for the level being played
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_blocks(mut commands: Commands,
+                    mesh_assets: ResMut<Assets<Mesh>>,
+                    material_assets: ResMut<Assets<ColorMaterial>>,
+                    level: Res<CurrentLevel>,
+                    levels: Res<LevelData>,
+                    dimensions: Res<GameDimensions>,
+                    config: Res<GameConfig>,
+                    difficulty: Res<Difficulty>) {
+
+    spawn_level(&mut commands, mesh_assets, material_assets, &levels.levels[level.0], &dimensions, &config, *difficulty);
+}
+
+pub fn spawn_level(commands: &mut Commands,
+                   mut mesh_assets: ResMut<Assets<Mesh>>,
+                   mut material_assets: ResMut<Assets<ColorMaterial>>,
+                   layout: &LevelLayout,
+                   dimensions: &GameDimensions,
+                   config: &GameConfig,
+                   difficulty: Difficulty) {
+
+    let block_mesh = mesh_assets.add(Rectangle::new(config.block_width, config.block_height));
+    // One color per row, shared by every block in it, getting darker as they take damage
+    let block_materials = BlockMaterials((0..layout.rows.len())
+        .map(|row| {
+            let (r, g, b) = ROW_COLORS[row % ROW_COLORS.len()];
+            [0.45, 0.7, 1.0].iter()
+                .map(|shade| material_assets.add(Color::srgb(r * shade, g * shade, b * shade)))
+                .collect()
+        })
+        .collect());
+    let indestructible_material = material_assets.add(Color::srgb(0.7, 0.7, 0.7));
+    let explosive_material = material_assets.add(Color::srgb(1.0, 0.45, 0.0));
+
+    for (row, cells) in layout.rows.iter().enumerate() {
+        let center = (cells.len() as f32 - 1.0) / 2.0; // Center the grid horizontally
+        let column_x = |column: usize| (column as f32 - center) * (config.block_width + 15.0); // Position blocks in a grid
+        // Every moving block in a row gets the range of the one nearest a wall, so they slide together
+        let widest = cells.iter().enumerate()
+            .filter(|(_, cell)| matches!(cell, Cell::Moving(_)))
+            .map(|(column, _)| column_x(column).abs())
+            .fold(0.0, f32::max);
+        let moving_range = (dimensions.width / 2.0 - config.block_width / 2.0 - widest).clamp(0.0, config.moving_block_range());
+
+        for (column, cell) in cells.iter().enumerate() {
+            let position = Transform::from_xyz(
+                column_x(column),
+                dimensions.top_row_y() - row as f32 * (config.block_height + 10.0),
+                0.0,
+            );
+            match *cell {
+                Cell::Empty => {} // Gap in the layout
+                Cell::Block(hits) => {
+                    let durability = difficulty.block_durability(hits, row);
+                    commands.spawn((
+                        Block,
+                        BlockRow(row),
+                        Durability(durability),
+                        PointValue(row_points(row, layout.rows.len())),
+                        DespawnOnGameOver, // This component will be used to despawn blocks on game over
+                        position,
+                        Mesh2d(block_mesh.clone()),
+                        MeshMaterial2d(block_materials.0[row][durability as usize - 1].clone()),
+                    ));
+                }
+                Cell::Indestructible => {
+                    commands.spawn((
+                        Block,
+                        Indestructible,
+                        Durability(1), // Never goes down
+                        PointValue(0),
+                        DespawnOnGameOver,
+                        position,
+                        Mesh2d(block_mesh.clone()),
+                        MeshMaterial2d(indestructible_material.clone()),
+                    ));
+                }
+                Cell::Moving(hits) => {
+                    let durability = difficulty.block_durability(hits, row);
+                    commands.spawn((
+                        Block,
+                        Moving {
+                            speed: MOVING_BLOCK_SPEED,
+                            range: moving_range,
+                            origin_x: position.translation.x,
+                            step: 0.0,
+                        },
+                        BlockRow(row),
+                        Durability(durability),
+                        PointValue(row_points(row, layout.rows.len())),
+                        DespawnOnGameOver,
+                        position,
+                        Mesh2d(block_mesh.clone()),
+                        MeshMaterial2d(block_materials.0[row][durability as usize - 1].clone()),
+                    ));
+                }
+                Cell::Explosive => {
+                    commands.spawn((
+                        Block,
+                        Explosive,
+                        BlockRow(row),
+                        Durability(1), // Always goes off on the first hit
+                        PointValue(row_points(row, layout.rows.len())),
+                        DespawnOnGameOver,
+                        position,
+                        Mesh2d(block_mesh.clone()),
+                        MeshMaterial2d(explosive_material.clone()),
+                    ));
+                }
+            }
+        }
+    }
+
+    commands.insert_resource(block_materials);
+}
+
+// Slide moving blocks back and forth around where they started, turning at the end of their range or a wall.
+// Runs in the fixed steps so it stops with virtual time and moves in step with the ball
+pub fn move_blocks(mut blocks: Query<(&mut Transform, &mut Moving), With<Block>>,
+                   dimensions: Res<GameDimensions>,
+                   config: Res<GameConfig>,
+                   time: Res<Time>) {
+
+    let max_x = dimensions.width / 2.0 - config.block_width / 2.0;
+    for (mut transform, mut moving) in blocks.iter_mut() {
+        let old_x = transform.translation.x;
+        let mut x = old_x + moving.speed * time.delta_secs();
+        let (min, max) = ((moving.origin_x - moving.range).max(-max_x), (moving.origin_x + moving.range).min(max_x));
+        if (x <= min && moving.speed < 0.0) || (x >= max && moving.speed > 0.0) {
+            moving.speed = -moving.speed; // Turn around
+        }
+        x = x.clamp(min.min(max), max.max(min)); // A shrunk window can leave no room at all
+        transform.translation.x = x;
+        moving.step = x - old_x;
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)] // Bevy queries get long
+pub fn block_collision(mut blocks: Query<(Entity, &Transform, &mut Durability, &PointValue, &mut MeshMaterial2d<ColorMaterial>, &BlockRow, Has<Indestructible>, Has<Explosive>, Option<&Moving>), With<Block>>,
+                       mut ball: Query<(&mut Transform, &PreviousPosition, &mut Velocity, &mut BallSpeed), (With<Ball>, Without<Block>, Without<StuckToPaddle>)>,
+                       block_materials: Res<BlockMaterials>,
+                       mut destroyed_events: EventWriter<BlockDestroyedEvent>,
+                       mut explosion_events: EventWriter<ExplosionEvent>,
+                       mut shake: ResMut<ScreenShake>,
+                       config: Res<GameConfig>,
+                       mut commands: Commands) {
+
+    let mut grid = BlockGrid::new(config.block_size());
+    for (block_entity, block_tf, ..) in blocks.iter() {
+        grid.insert(block_entity, block_tf.translation.truncate());
+    }
+
+    for (mut ball_tf, previous, mut vel, mut speed) in ball.iter_mut() {
+        let start = previous.0;
+        let end = ball_tf.translation.truncate();
+
+        // Only bounce off the first block along the path, hitting a seam between two blocks
+        // would otherwise reflect the ball twice and send it straight through
+        let mut closest: Option<(f32, Vec2, Vec2, Vec2, Entity)> = None;
+        for nearby_block in grid.nearby(start, end, config.ball_size) {
+            let Ok((block_entity, block_tf, durability, .., moving)) = blocks.get(nearby_block) else {
+                continue;
+            };
+            if durability.0 == 0 {
+                continue; // Already broken this frame
+            }
+
+            // Sweep the ball's path as seen from the block, so a moving block can't jump over the ball either
+            let (step, block_vel) = moving.map_or((Vec2::ZERO, Vec2::ZERO), |moving| (Vec2::new(moving.step, 0.0), Vec2::new(moving.speed, 0.0)));
+            let relative_start = start + step;
+
+            // Check the whole path so a fast ball can't pass through a block between frames
+            if let Some((t, normal)) = sweep_hit(relative_start,
+                                                 end,
+                                                 Vec2::splat(config.ball_size / 2.0),
+                                                 block_tf.translation.truncate(),
+                                                 config.block_size() / 2.0) {
+
+                if (vel.0 - block_vel).dot(normal) >= 0.0 {
+                    continue; // Already bouncing away, don't hit the same block twice
+                }
+                if closest.is_none_or(|(closest_t, ..)| t < closest_t) {
+                    closest = Some((t, normal, relative_start, block_vel, block_entity));
+                }
+            }
+        }
+
+        let Some((t, normal, relative_start, block_vel, block_entity)) = closest else {
+            continue;
+        };
+        let contact = relative_start.lerp(end, t); // Where the ball touched the block
+        ball_tf.translation.x = contact.x;
+        ball_tf.translation.y = contact.y;
+        vel.0 = reflect(vel.0 - block_vel, normal) + block_vel; // Bounce the ball off the face it hit, a moving block pushes it along
+
+        let Ok((_, block_tf, mut durability, points, mut material, row, indestructible, explosive, _)) = blocks.get_mut(block_entity) else {
+            continue;
+        };
+        if indestructible {
+            continue; // Just a bounce
+        }
+        durability.0 -= 1;
+        if durability.0 > 0 {
+            material.0 = block_materials.0[row.0][durability.0 as usize - 1].clone(); // Show the damage
+            continue;
+        }
+
+        commands.entity(block_entity).despawn(); // Remove the block
+        shake.start(speed.0);
+        speed.0 = speed_up(speed.0); // Breaking blocks makes the game harder
+        destroyed_events.write(BlockDestroyedEvent {
+            entity: block_entity,
+            position: block_tf.translation,
+            value: points.0,
+            material: material.0.clone(),
+        }); // Scoring and effects react to this
+        if explosive {
+            explosion_events.write(ExplosionEvent { position: block_tf.translation });
+        }
+    }
+}
+
+// Break every block an explosion reaches. Explosive ones among them go off on the next frame,
+// so a chain ripples outwards and each block is only broken, and scored, once
+#[allow(clippy::type_complexity)]
+pub fn explode_blocks(mut commands: Commands,
+                      mut explosions: EventReader<ExplosionEvent>,
+                      mut blocks: Query<(Entity, &Transform, &mut Durability, &PointValue, &MeshMaterial2d<ColorMaterial>, Has<Explosive>), (With<Block>, Without<Indestructible>)>,
+                      mut destroyed_events: EventWriter<BlockDestroyedEvent>,
+                      config: Res<GameConfig>) {
+
+    for explosion in explosions.read() {
+        for (block_entity, block_tf, mut durability, points, material, explosive) in blocks.iter_mut() {
+            if durability.0 == 0 {
+                continue; // Already broken, by this blast or anything else this frame
+            }
+            if block_tf.translation.truncate().distance(explosion.position.truncate()) > config.blast_radius() {
+                continue;
+            }
+
+            durability.0 = 0;
+            commands.entity(block_entity).despawn();
+            destroyed_events.write(BlockDestroyedEvent {
+                entity: block_entity,
+                position: block_tf.translation,
+                value: points.0,
+                material: material.0.clone(),
+            });
+            if explosive {
+                commands.send_event(ExplosionEvent { position: block_tf.translation });
+            }
+        }
+    }
+}
+
+// Top rows are worth more, from one point for the bottom row up to one per row for the top
+pub fn row_points(row: usize, rows: usize) -> u32 {
+    rows.saturating_sub(row).max(1) as u32
+}
+
+// Slightly faster speed, capped at MAX_BALL_SPEED
+pub fn speed_up(speed: f32) -> f32 {
+    (speed * BALL_SPEEDUP).min(MAX_BALL_SPEED)
+}
+
+// Blocks bucketed by grid cell, so each ball only checks the blocks around it
+pub struct BlockGrid {
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+    block_size: Vec2, // Size of one cell
+}
+
+impl BlockGrid {
+    pub fn new(block_size: Vec2) -> Self {
+        BlockGrid { cells: HashMap::new(), block_size }
+    }
+
+    pub fn insert(&mut self, block: Entity, position: Vec2) {
+        self.cells.entry(self.cell(position)).or_default().push(block);
+    }
+
+    // Blocks a ball of this size could touch moving from start to end
+    pub fn nearby(&self, start: Vec2, end: Vec2, ball_size: f32) -> impl Iterator<Item = Entity> + '_ {
+        // A ball can only touch blocks whose center is within half a ball and half a block of its path,
+        // a ball bigger than a block reaches more than one cell past it
+        let (min_x, min_y) = self.cell(start.min(end));
+        let (max_x, max_y) = self.cell(start.max(end));
+        let reach = ((Vec2::splat(ball_size) + self.block_size) / 2.0 / self.block_size).ceil().as_ivec2();
+        (min_x - reach.x..=max_x + reach.x)
+            .flat_map(move |x| (min_y - reach.y..=max_y + reach.y).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+
+    // Grid cell a position falls in, each cell is the size of one block
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        ((position.x / self.block_size.x).floor() as i32, (position.y / self.block_size.y).floor() as i32)
+    }
+}
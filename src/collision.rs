@@ -0,0 +1,91 @@
+// Box collision math, plain functions on positions and sizes so they can be tested without an App.
+// Boxes are given by their center and half their size
+use bevy::math::Vec2;
+
+use crate::CORNER_TOLERANCE;
+
+// Whether the boxes touch, boxes that only share an edge count
+pub fn aabb_overlap(a_center: Vec2, a_half: Vec2, b_center: Vec2, b_half: Vec2) -> bool {
+    let gap = (a_center - b_center).abs() - (a_half + b_half);
+    gap.x <= 0.0 && gap.y <= 0.0
+}
+
+// Normal of the face of box b that box a hit, None if they don't overlap
+// Corner hits resolve on the axis with the smaller overlap, or both if they're about equal
+pub fn hit_normal(a_center: Vec2, a_half: Vec2, b_center: Vec2, b_half: Vec2) -> Option<Vec2> {
+    if !aabb_overlap(a_center, a_half, b_center, b_half) {
+        return None;
+    }
+    let delta = a_center - b_center;
+    let overlap = a_half + b_half - delta.abs();
+
+    if (overlap.x - overlap.y).abs() < CORNER_TOLERANCE {
+        Some(delta.signum()) // Hit right on the corner
+    } else if overlap.x < overlap.y {
+        Some(Vec2::new(delta.x.signum(), 0.0)) // Hit the left or right side
+    } else {
+        Some(Vec2::new(0.0, delta.y.signum())) // Hit the top or bottom
+    }
+}
+
+// First contact of box a moving from start to end with box b
+// Returns how far along the path the hit happened (0.0 to 1.0) and the normal of the face hit
+pub fn sweep_hit(start: Vec2, end: Vec2, a_half: Vec2, b_center: Vec2, b_half: Vec2) -> Option<(f32, Vec2)> {
+    // Grow box b by box a's size so the moving box can be treated as a point
+    let min = b_center - (a_half + b_half);
+    let max = b_center + (a_half + b_half);
+    let delta = end - start;
+
+    let mut t_enter = 0.0_f32;
+    let mut t_exit = 1.0_f32;
+    let mut t_nears = [f32::NEG_INFINITY; 2];
+    let mut normal = Vec2::ZERO;
+
+    for axis in 0..2 {
+        if delta[axis] == 0.0 {
+            if start[axis] < min[axis] || start[axis] > max[axis] {
+                return None; // Not moving on this axis and outside the box
+            }
+            continue;
+        }
+
+        let t_min = (min[axis] - start[axis]) / delta[axis];
+        let t_max = (max[axis] - start[axis]) / delta[axis];
+        let (t_near, t_far) = if t_min < t_max { (t_min, t_max) } else { (t_max, t_min) };
+        t_nears[axis] = t_near;
+
+        if t_near > t_enter {
+            t_enter = t_near;
+            normal = Vec2::ZERO;
+            normal[axis] = -delta[axis].signum(); // Face pointing against the movement
+        }
+        t_exit = t_exit.min(t_far);
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+
+    if normal == Vec2::ZERO {
+        // Already overlapping at the start of the path
+        return hit_normal(start, a_half, b_center, b_half).map(|normal| (0.0, normal));
+    }
+
+    // Entering both faces at about the same time means the corner was hit
+    for axis in 0..2 {
+        if normal[axis] == 0.0 && (t_enter - t_nears[axis]) * delta[axis].abs() < CORNER_TOLERANCE {
+            normal[axis] = -delta[axis].signum();
+        }
+    }
+    Some((t_enter, normal))
+}
+
+// Flip each velocity component that's moving into the face the normal points out of
+pub fn reflect(velocity: Vec2, normal: Vec2) -> Vec2 {
+    let mut reflected = velocity;
+    for axis in 0..2 {
+        if velocity[axis] * normal[axis] < 0.0 {
+            reflected[axis] = -velocity[axis];
+        }
+    }
+    reflected
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::*;
+
+// Sizes and speeds the game is tuned with, read from CONFIG_FILE on startup so they can be changed without recompiling
+#[derive(Resource, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)] // Fields left out of the file keep their defaults
+pub struct GameConfig {
+    pub paddle_width: f32, // On Normal difficulty
+    pub paddle_speed: f32, // Pixels per second
+    pub ball_size: f32,
+    pub ball_start_speed: f32, // On Normal difficulty
+    pub block_width: f32,
+    pub block_height: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            paddle_width: PLAYER_SIZE,
+            paddle_speed: PADDLE_SPEED,
+            ball_size: BALL_SIZE,
+            ball_start_speed: BALL_START_SPEED,
+            block_width: BLOCK_WIDTH,
+            block_height: BLOCK_HEIGHT,
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    // Put the defaults back for values the game can't use, returning the names of the fields that were replaced
+    pub fn validated(mut self) -> (Self, Vec<&'static str>) {
+        let defaults = GameConfig::default();
+        let mut rejected = Vec::new();
+        // Largest value each field can take, every one of them has to be above zero
+        let checks = [
+            ("paddle_width", &mut self.paddle_width, defaults.paddle_width, MIN_WINDOW_WIDTH / 1.25), // Widest on Easy
+            ("paddle_speed", &mut self.paddle_speed, defaults.paddle_speed, f32::MAX),
+            ("ball_size", &mut self.ball_size, defaults.ball_size, MIN_WINDOW_HEIGHT / 2.0),
+            ("ball_start_speed", &mut self.ball_start_speed, defaults.ball_start_speed, MAX_BALL_SPEED),
+            ("block_width", &mut self.block_width, defaults.block_width, MIN_WINDOW_WIDTH),
+            ("block_height", &mut self.block_height, defaults.block_height, MIN_WINDOW_HEIGHT / 2.0),
+        ];
+        for (name, value, default, max) in checks {
+            if !(*value > 0.0 && *value <= max) { // NaN fails both
+                *value = default;
+                rejected.push(name);
+            }
+        }
+        (self, rejected)
+    }
+
+    pub fn block_size(&self) -> Vec2 {
+        Vec2::new(self.block_width, self.block_height)
+    }
+
+    // Both scale with the blocks, so bigger blocks don't leave explosions and moving blocks behind
+    pub fn blast_radius(&self) -> f32 {
+        self.block_width * BLAST_RADIUS
+    }
+
+    pub fn moving_block_range(&self) -> f32 {
+        self.block_width * MOVING_BLOCK_RANGE
+    }
+}
+
+// Read the config file, a missing one means the defaults and a broken one is reported and ignored
+pub fn load_config(mut commands: Commands) {
+    let config = match fs::read_to_string(CONFIG_FILE) {
+        Ok(text) => GameConfig::from_ron(&text).unwrap_or_else(|err| {
+            error!("Ignoring {CONFIG_FILE}: {err}");
+            GameConfig::default()
+        }),
+        Err(_) => GameConfig::default(),
+    };
+    let (config, rejected) = config.validated();
+    for field in rejected {
+        error!("{CONFIG_FILE}: {field} is out of range, using the default");
+    }
+    commands.insert_resource(config);
+}
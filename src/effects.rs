@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+
+use crate::ball::BallTrail;
+use crate::blocks::{Block, BlockDestroyedEvent, Durability, ExplosionEvent, Indestructible};
+use crate::config::GameConfig;
+use crate::settings::Settings;
+use crate::state::BallLostEvent;
+use crate::*;
+
+#[derive(Resource, Default)]
+pub struct ScreenShake {
+    pub remaining: f32, // Seconds left to shake
+    pub intensity: f32, // Furthest the camera moves, in pixels, at the start of the shake
+}
+
+#[derive(Resource)]
+pub struct HitPause(pub Timer); // Game time is frozen until this runs out, counted in real time
+
+#[derive(Component)]
+#[require(Velocity)]
+pub struct Particle;
+
+#[derive(Component)]
+pub struct Lifetime(pub Timer); // Counts down until the entity disappears
+
+#[derive(Component)]
+pub struct Blast; // Ring growing out of an explosion
+
+#[derive(Resource)]
+pub struct ParticleAssets {
+    pub mesh: Handle<Mesh>,
+    pub blast_mesh: Handle<Mesh>, // Circle of radius 1, scaled up as it grows
+    pub blast_material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+pub struct TrailDot; // One faded copy of a ball along its trail
+
+#[derive(Resource)]
+pub struct TrailAssets {
+    pub mesh: Handle<Mesh>,
+    pub materials: Vec<Handle<ColorMaterial>>, // One per trail position, fading with age
+}
+
+impl ScreenShake {
+    // Start shaking, harder for faster balls
+    pub fn start(&mut self, ball_speed: f32) {
+        self.kick(SHAKE_INTENSITY * ball_speed / BALL_START_SPEED);
+    }
+
+    // Start over at `intensity` pixels, unless what's left of the current shake is stronger
+    pub fn kick(&mut self, intensity: f32) {
+        self.intensity = intensity.max(self.strength());
+        self.remaining = SHAKE_DURATION;
+    }
+
+    // How far the camera can move right now, dying away as the shake runs out
+    pub fn strength(&self) -> f32 {
+        self.intensity * self.remaining / SHAKE_DURATION
+    }
+}
+
+// Losing a ball shakes the screen much harder than breaking a block
+pub fn shake_on_ball_lost(mut events: EventReader<BallLostEvent>,
+                          mut shake: ResMut<ScreenShake>) {
+
+    if events.read().count() > 0 {
+        shake.kick(BALL_LOST_SHAKE);
+    }
+}
+
+// Move the camera a random amount around the center, less as the shake runs out
+pub fn camera_shake(mut shake: ResMut<ScreenShake>,
+                    mut camera: Query<&mut Transform, With<Camera2d>>,
+                    settings: Res<Settings>,
+                    time: Res<Time>) {
+
+    let Ok(mut camera_tf) = camera.single_mut() else {
+        return;
+    };
+    if settings.reduce_motion {
+        shake.remaining = 0.0; // Never starts
+    }
+    if shake.remaining <= 0.0 {
+        camera_tf.translation.x = 0.0; // Back to the center once it's over
+        camera_tf.translation.y = 0.0;
+        return;
+    }
+
+    shake.remaining = (shake.remaining - time.delta_secs()).max(0.0);
+    let strength = shake.strength();
+    let offset = Vec2::new(rand::random::<f32>() * 2.0 - 1.0, rand::random::<f32>() * 2.0 - 1.0) * strength;
+    // Always measured from the center so the camera can't drift
+    camera_tf.translation.x = offset.x;
+    camera_tf.translation.y = offset.y;
+}
+
+// Freeze the game for a moment when a block was the last one standing in its column.
+// Clearing the whole level doesn't, the level transition has its own pause
+#[allow(clippy::type_complexity)]
+pub fn hit_pause_on_column_clear(mut commands: Commands,
+                                 mut events: EventReader<BlockDestroyedEvent>,
+                                 blocks: Query<(&Transform, &Durability), (With<Block>, Without<Indestructible>)>,
+                                 settings: Res<Settings>,
+                                 config: Res<GameConfig>,
+                                 mut time: ResMut<Time<Virtual>>) {
+
+    let mut standing = blocks.iter().filter(|(_, durability)| durability.0 > 0); // Not the ones broken this frame
+    // Counted rather than any(), which would stop early and leave the rest to be checked against next frame's blocks
+    let column_cleared = events.read().filter(|event| {
+        !standing.clone().any(|(block_tf, _)| (block_tf.translation.x - event.position.x).abs() < config.block_width / 2.0)
+    }).count() > 0;
+    if !column_cleared || settings.reduce_motion || standing.next().is_none() {
+        return;
+    }
+    commands.insert_resource(HitPause(Timer::from_seconds(HIT_PAUSE, TimerMode::Once)));
+    time.pause();
+}
+
+// Let game time run again once the hit pause is over. It only counts down while playing,
+// so pausing in the middle of one leaves the pause menu in charge of the clock
+pub fn hit_pause_update(mut commands: Commands,
+                        mut hit_pause: ResMut<HitPause>,
+                        mut time: ResMut<Time<Virtual>>,
+                        real_time: Res<Time<Real>>) {
+
+    if hit_pause.0.tick(real_time.delta()).finished() {
+        time.unpause();
+        commands.remove_resource::<HitPause>();
+    }
+}
+
+// Burst every broken block into a few small squares of its color
+pub fn spawn_particles(mut commands: Commands,
+                       mut events: EventReader<BlockDestroyedEvent>,
+                       assets: Res<ParticleAssets>) {
+
+    for event in events.read() {
+        for _ in 0..PARTICLES_PER_BLOCK {
+            let direction = Vec2::from_angle(rand::random::<f32>() * std::f32::consts::TAU);
+            let speed = PARTICLE_SPEED * (0.5 + rand::random::<f32>() * 0.5);
+            commands.spawn((
+                Particle,
+                DespawnOnGameOver,
+                Lifetime(Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once)),
+                Transform::from_translation(event.position),
+                Velocity(direction * speed),
+                Mesh2d(assets.mesh.clone()),
+                MeshMaterial2d(event.material.clone()),
+            ));
+        }
+    }
+}
+
+// Move particles outwards, pull them down and shrink them away as their time runs out
+pub fn particle_update(mut commands: Commands,
+                       mut particles: Query<(Entity, &mut Transform, &mut Velocity, &mut Lifetime), With<Particle>>,
+                       time: Res<Time>) {
+
+    for (entity, mut transform, mut vel, mut lifetime) in particles.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        vel.0.y -= PARTICLE_GRAVITY * time.delta_secs();
+        transform.translation.x += vel.0.x * time.delta_secs();
+        transform.translation.y += vel.0.y * time.delta_secs();
+        transform.scale = Vec3::splat(lifetime.0.fraction_remaining()); // Shrinking reads as fading without a material per particle
+    }
+}
+
+// Start a growing circle where each explosion went off
+pub fn spawn_blasts(mut commands: Commands,
+                    mut events: EventReader<ExplosionEvent>,
+                    assets: Res<ParticleAssets>) {
+
+    for event in events.read() {
+        commands.spawn((
+            Blast,
+            DespawnOnGameOver,
+            Lifetime(Timer::from_seconds(BLAST_LIFETIME, TimerMode::Once)),
+            Transform::from_translation(event.position.with_z(0.5)).with_scale(Vec3::ZERO), // Over the blocks
+            Mesh2d(assets.blast_mesh.clone()),
+            MeshMaterial2d(assets.blast_material.clone()),
+        ));
+    }
+}
+
+// Grow blasts out to the radius they reach, then remove them
+pub fn blast_update(mut commands: Commands,
+                    mut blasts: Query<(Entity, &mut Transform, &mut Lifetime), With<Blast>>,
+                    config: Res<GameConfig>,
+                    time: Res<Time>) {
+
+    for (entity, mut transform, mut lifetime) in blasts.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.scale = Vec3::splat(config.blast_radius() * lifetime.0.fraction());
+    }
+}
+
+// Put a faded, shrinking dot on every remembered ball position, reusing last frame's dots where there are enough.
+// It only runs while playing, so a paused game keeps its trails frozen in place
+pub fn render_trail(mut commands: Commands,
+                    trails: Query<&BallTrail>,
+                    mut dots: Query<(Entity, &mut Transform, &mut MeshMaterial2d<ColorMaterial>), With<TrailDot>>,
+                    assets: Res<TrailAssets>) {
+
+    let mut dots = dots.iter_mut();
+    for trail in trails.iter() {
+        for (age, position) in trail.0.iter().enumerate() {
+            let transform = Transform::from_translation(position.extend(-0.5)) // Behind the ball
+                .with_scale(Vec3::splat(1.0 - 0.5 * age as f32 / TRAIL_LENGTH as f32)); // Oldest at about half size
+            let material = assets.materials[age.min(assets.materials.len() - 1)].clone();
+            if let Some((_, mut dot_tf, mut dot_material)) = dots.next() {
+                *dot_tf = transform;
+                dot_material.0 = material;
+            } else {
+                commands.spawn((
+                    TrailDot,
+                    DespawnOnGameOver,
+                    transform,
+                    Mesh2d(assets.mesh.clone()),
+                    MeshMaterial2d(material),
+                ));
+            }
+        }
+    }
+    for (entity, _, _) in dots {
+        commands.entity(entity).despawn(); // Left over from a shorter trail or a lost ball
+    }
+}
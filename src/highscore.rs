@@ -0,0 +1,75 @@
+use std::fs;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::state::Difficulty;
+use crate::ui::Score;
+use crate::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct HighScore {
+    pub score: u32,
+    pub difficulty: Difficulty, // What the game was played on
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct HighScores {
+    pub scores: Vec<HighScore>, // Best first, at most MAX_HIGH_SCORES
+}
+
+impl HighScores {
+    // Add a finished game's score, keeping only the best ones
+    pub fn insert(&mut self, score: u32, difficulty: Difficulty) {
+        self.scores.push(HighScore { score, difficulty });
+        self.scores.sort_by_key(|high_score| std::cmp::Reverse(high_score.score)); // Stable, an equal older score stays ahead
+        self.scores.truncate(MAX_HIGH_SCORES);
+    }
+
+    pub fn best(&self) -> u32 {
+        self.scores.first().map_or(0, |high_score| high_score.score)
+    }
+
+    // Beats every score on the table
+    pub fn is_record(&self, score: u32) -> bool {
+        score > self.best()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    // A corrupt file gives an empty table instead of an error
+    pub fn from_json(json: &str) -> Self {
+        let mut high_scores: HighScores = serde_json::from_str(json).unwrap_or_default();
+        high_scores.scores.sort_by_key(|high_score| std::cmp::Reverse(high_score.score)); // Don't trust a hand edited file
+        high_scores.scores.truncate(MAX_HIGH_SCORES);
+        high_scores
+    }
+}
+
+// Read the table from disk, a missing file just means nobody has played yet
+pub fn load_high_scores(mut commands: Commands) {
+    let high_scores = data_file("highscores.json")
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|json| HighScores::from_json(&json))
+        .unwrap_or_default();
+    commands.insert_resource(high_scores);
+}
+
+// Put the final score on the table and save it, runs after the end screen text has read the old best
+pub fn record_high_score(mut high_scores: ResMut<HighScores>,
+                         difficulty: Res<Difficulty>,
+                         score: Res<Score>) {
+
+    high_scores.insert(score.0, *difficulty);
+
+    let Some(path) = data_file("highscores.json") else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(err) = fs::write(&path, high_scores.to_json()) {
+        warn!("Couldn't save high scores to {}: {err}", path.display()); // Not worth stopping the game over
+    }
+}
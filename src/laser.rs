@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::ball::StuckToPaddle;
+use crate::collision::aabb_overlap;
+use crate::config::GameConfig;
+use crate::blocks::{Block, BlockDestroyedEvent, Durability, Explosive, ExplosionEvent, Indestructible, PointValue};
+use crate::paddle::{PaddleWidth, Player};
+use crate::settings::KeyBindings;
+use crate::*;
+
+#[derive(Component)]
+#[require(Velocity)]
+pub struct Bullet; // Fired straight up from the paddle, breaks the first block it touches
+
+#[derive(Component)]
+pub struct LaserPaddle {
+    pub timer: Timer, // Counts down until the paddle stops shooting
+    pub cooldown: Timer, // Time left before the next shot
+}
+
+#[derive(Resource)]
+pub struct BulletAssets {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<ColorMaterial>,
+}
+
+// Shoot a bullet from each end of the paddle with the serve key or a left click
+#[allow(clippy::too_many_arguments)]
+pub fn fire_laser(mut commands: Commands,
+                  mut player: Query<(&Transform, &PaddleWidth, &mut LaserPaddle), With<Player>>,
+                  stuck: Query<(), With<StuckToPaddle>>,
+                  assets: Res<BulletAssets>,
+                  time: Res<Time>,
+                  keyboard_input: Res<ButtonInput<KeyCode>>,
+                  bindings: Res<KeyBindings>,
+                  mouse_input: Res<ButtonInput<MouseButton>>) {
+
+    let Ok((player_tf, width, mut laser)) = player.single_mut() else {
+        return;
+    };
+    laser.cooldown.tick(time.delta());
+
+    if !laser.cooldown.finished() || !stuck.is_empty() {
+        return; // The serve key launches a waiting ball instead
+    }
+    if !keyboard_input.pressed(bindings.serve) && !mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+
+    for side in [-1.0, 1.0] {
+        commands.spawn((
+            Bullet,
+            DespawnOnGameOver,
+            Transform::from_xyz(player_tf.translation.x + side * (width.0 / 2.0 - BULLET_SIZE.x),
+                                player_tf.translation.y + PLAYER_WIDTH / 2.0 + BULLET_SIZE.y / 2.0,
+                                0.0),
+            Velocity(Vec2::new(0.0, BULLET_SPEED)),
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(assets.material.clone()),
+        ));
+    }
+    laser.cooldown = Timer::from_seconds(LASER_COOLDOWN, TimerMode::Once);
+}
+
+pub fn bullet_movement(mut commands: Commands,
+                       mut bullets: Query<(Entity, &mut Transform, &Velocity), With<Bullet>>,
+                       dimensions: Res<GameDimensions>,
+                       time: Res<Time>) {
+
+    for (entity, mut transform, vel) in bullets.iter_mut() {
+        transform.translation.y += vel.0.y * time.delta_secs();
+
+        if transform.translation.y > dimensions.height / 2.0 + BULLET_SIZE.y {
+            commands.entity(entity).despawn(); // Missed everything, left the top of the screen
+        }
+    }
+}
+
+// Break the block a bullet touches, scored the same way as a ball breaking it
+#[allow(clippy::type_complexity)]
+pub fn bullet_collision(mut commands: Commands,
+                        bullets: Query<(Entity, &Transform), With<Bullet>>,
+                        mut blocks: Query<(Entity, &Transform, &mut Durability, &PointValue, &MeshMaterial2d<ColorMaterial>, Has<Indestructible>, Has<Explosive>), With<Block>>,
+                        mut destroyed_events: EventWriter<BlockDestroyedEvent>,
+                        mut explosion_events: EventWriter<ExplosionEvent>,
+                        config: Res<GameConfig>) {
+
+    for (bullet_entity, bullet_tf) in bullets.iter() {
+        for (block_entity, block_tf, mut durability, points, material, indestructible, explosive) in blocks.iter_mut() {
+            if durability.0 == 0 {
+                continue; // Already broken this frame
+            }
+            if !aabb_overlap(bullet_tf.translation.truncate(),
+                             BULLET_SIZE / 2.0,
+                             block_tf.translation.truncate(),
+                             config.block_size() / 2.0) {
+                continue;
+            }
+
+            commands.entity(bullet_entity).despawn();
+            if indestructible {
+                break; // Stopped without breaking it
+            }
+            durability.0 = 0; // Lasers break blocks in one shot
+            commands.entity(block_entity).despawn();
+            destroyed_events.write(BlockDestroyedEvent {
+                entity: block_entity,
+                position: block_tf.translation,
+                value: points.0,
+                material: material.0.clone(),
+            });
+            if explosive {
+                explosion_events.write(ExplosionEvent { position: block_tf.translation });
+            }
+            break; // One block per bullet
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+// One character of a level file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell {
+    Empty, // '.'
+    Block(u32), // '1' to '3', hits the block takes
+    Indestructible, // '#', the ball bounces off but it never breaks
+    Explosive, // '*', breaks in one hit and takes the blocks around it along
+    Moving(u32), // 'a' to 'c' for 1 to 3 hits, or any block in a row ending in '~', slides from side to side
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelLayout {
+    pub rows: Vec<Vec<Cell>>, // Top row first, every row the same length
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LayoutError {
+    NoBlocks, // Nothing to break, the level could never be won
+    RaggedRow { line: usize, expected: usize, found: usize },
+    UnknownCharacter { line: usize, column: usize, character: char },
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::NoBlocks => write!(f, "the level has no breakable blocks"),
+            LayoutError::RaggedRow { line, expected, found } =>
+                write!(f, "line {line} is {found} cells wide, expected {expected}"),
+            LayoutError::UnknownCharacter { line, column, character } =>
+                write!(f, "unknown character '{character}' at line {line}, column {column}"),
+        }
+    }
+}
+
+impl LevelLayout {
+    // Blocks that have to be broken to clear the level
+    pub fn breakable_blocks(&self) -> usize {
+        self.rows.iter().flatten().filter(|cell| matches!(cell, Cell::Block(_) | Cell::Explosive | Cell::Moving(_))).count()
+    }
+}
+
+// Parse a text grid, blank lines are ignored and line numbers count from 1
+pub fn parse_layout(text: &str) -> Result<LevelLayout, LayoutError> {
+    let mut rows: Vec<Vec<Cell>> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end(); // Editors like to leave trailing spaces and \r
+        if line.is_empty() {
+            continue;
+        }
+
+        // A '~' after the last cell sets every block in the row moving
+        let (line, moving_row) = match line.strip_suffix('~') {
+            Some(cells) => (cells, true),
+            None => (line, false),
+        };
+
+        let row = line.chars().enumerate()
+            .map(|(column, character)| match character {
+                '.' => Ok(Cell::Empty),
+                '1'..='3' => Ok(Cell::Block(character.to_digit(10).unwrap_or(1))),
+                '#' => Ok(Cell::Indestructible),
+                '*' => Ok(Cell::Explosive),
+                'a'..='c' => Ok(Cell::Moving(character as u32 - 'a' as u32 + 1)),
+                _ => Err(LayoutError::UnknownCharacter { line: index + 1, column: column + 1, character }),
+            })
+            .map(|cell| match cell {
+                Ok(Cell::Block(hits)) if moving_row => Ok(Cell::Moving(hits)),
+                cell => cell,
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(first) = rows.first()
+            && first.len() != row.len() {
+            return Err(LayoutError::RaggedRow { line: index + 1, expected: first.len(), found: row.len() });
+        }
+        rows.push(row);
+    }
+
+    let layout = LevelLayout { rows };
+    if layout.breakable_blocks() == 0 {
+        return Err(LayoutError::NoBlocks);
+    }
+    Ok(layout)
+}
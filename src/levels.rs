@@ -0,0 +1,57 @@
+use std::fs;
+use bevy::prelude::*;
+
+use crate::layout::{parse_layout, LevelLayout};
+use crate::*;
+
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub usize); // Index into LevelData's levels, counting from 0
+
+#[derive(Resource, Debug, PartialEq)]
+pub struct LevelData {
+    pub levels: Vec<LevelLayout>, // Played in order
+}
+
+// Used when there are no level files. Built from the shipped files so the two can't drift apart
+pub const LEVELS: [&str; 4] = [
+    include_str!("../assets/levels/01.txt"), // The original wall, tougher towards the top
+    include_str!("../assets/levels/02.txt"), // Checkerboard
+    include_str!("../assets/levels/03.txt"), // Pyramid
+    include_str!("../assets/levels/04.txt"), // Two towers with a tough bridge
+];
+
+impl LevelData {
+    pub fn built_in() -> Self {
+        LevelData {
+            levels: LEVELS.iter()
+                .map(|text| parse_layout(text).expect("built in levels are valid"))
+                .collect(),
+        }
+    }
+}
+
+// Read every level file in LEVELS_DIR in name order, the built in levels are used if none of them load
+pub fn load_levels(mut commands: Commands) {
+    let mut paths: Vec<_> = fs::read_dir(LEVELS_DIR)
+        .map(|dir| dir.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect())
+        .unwrap_or_default(); // No custom levels
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "txt"));
+    paths.sort();
+
+    let mut levels = Vec::new();
+    for path in paths {
+        let layout = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| parse_layout(&text).map_err(|err| err.to_string()));
+        match layout {
+            Ok(layout) => levels.push(layout),
+            Err(err) => error!("Skipping level {}: {err}", path.display()),
+        }
+    }
+
+    if levels.is_empty() {
+        commands.insert_resource(LevelData::built_in());
+    } else {
+        commands.insert_resource(LevelData { levels });
+    }
+}
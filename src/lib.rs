@@ -0,0 +1,552 @@
+use std::path::PathBuf;
+use bevy::prelude::*;
+use bevy::input::InputSystem;
+use bevy::window::{PrimaryWindow, WindowMode, WindowResized};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub mod audio;
+pub mod ball;
+pub mod blocks;
+pub mod collision;
+pub mod config;
+pub mod effects;
+pub mod highscore;
+pub mod laser;
+pub mod layout;
+pub mod levels;
+pub mod paddle;
+pub mod powerup;
+pub mod settings;
+pub mod state;
+pub mod ui;
+
+use audio::*;
+use ball::*;
+use blocks::*;
+use config::*;
+use effects::*;
+use highscore::*;
+use laser::*;
+use levels::*;
+use paddle::*;
+use powerup::*;
+use settings::*;
+use state::*;
+use ui::*;
+
+#[derive(Component, Default)]
+pub struct Velocity(pub Vec2);
+
+#[derive(Component)]
+pub struct DespawnOnGameOver;
+
+// Size of the play area, the window is opened at this size
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct GameDimensions {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for GameDimensions {
+    fn default() -> Self {
+        GameDimensions {
+            width: WINDOW_WIDTH,
+            height: WINDOW_HEIGHT,
+        }
+    }
+}
+
+impl GameDimensions {
+    // Read `--width N` and `--height N`, missing or unusable values keep the defaults
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut dimensions = GameDimensions::default();
+        let args: Vec<String> = args.collect();
+        for pair in args.windows(2) {
+            let Ok(value) = pair[1].parse::<f32>() else {
+                continue;
+            };
+            match pair[0].as_str() {
+                "--width" if value >= MIN_WINDOW_WIDTH => dimensions.width = value,
+                "--height" if value >= MIN_WINDOW_HEIGHT => dimensions.height = value,
+                _ => {}
+            }
+        }
+        dimensions
+    }
+
+    // Height the paddle moves along
+    pub fn player_y(&self) -> f32 {
+        self.height / -2.0 + 50.0
+    }
+
+    // Height of the top row of blocks
+    pub fn top_row_y(&self) -> f32 {
+        self.height / 2.0 - 35.0
+    }
+}
+
+// Randomness that changes how the game plays out, the same seed replays the same way.
+// Purely visual effects like particles and screen shake don't use it, so the frame rate can't change the game
+#[derive(Resource)]
+pub struct GameRng {
+    pub rng: StdRng,
+    pub seed: u64, // Pass it back with --seed or RUSTOUT_SEED to replay a run
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    // Seed from `--seed N`, then the RUSTOUT_SEED environment variable, and a random one if neither is set
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut args = args.skip_while(|arg| arg != "--seed").skip(1);
+        args.next()
+            .or_else(|| std::env::var("RUSTOUT_SEED").ok())
+            .and_then(|seed| seed.parse().ok())
+            .map_or_else(GameRng::default, GameRng::new)
+    }
+}
+
+// Path of a file in the game's folder of the platform's data directory
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    let data_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else if let Some(xdg) = std::env::var_os("XDG_DATA_HOME") {
+        PathBuf::from(xdg)
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".local/share")
+    };
+    Some(data_dir.join("rustout").join(name))
+}
+
+// Path of a file in the game's folder of the platform's config directory, for files players edit by hand
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    let config_dir = if cfg!(target_os = "windows") {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME") {
+        PathBuf::from(xdg)
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+    Some(config_dir.join("rustout").join(name))
+}
+
+// Constants for the window size and player size
+pub const WINDOW_WIDTH: f32 = 1000.0; // Default play area size
+pub const WINDOW_HEIGHT: f32 = 700.0;
+pub const MIN_WINDOW_WIDTH: f32 = 900.0; // Smallest play area a five block wide level fits in
+pub const MIN_WINDOW_HEIGHT: f32 = 500.0;
+pub const PLAYER_SIZE: f32 = 200.0; // Paddle width on Normal difficulty
+pub const PLAYER_WIDTH: f32 = 15.0; // Thickness of the player paddle
+pub const BLOCK_HEIGHT: f32 = WINDOW_HEIGHT / 20.0; // Height of each blocks
+pub const BLOCK_WIDTH: f32 = WINDOW_WIDTH / 6.0; // Width of each block
+pub const VOLUME_STEP: f32 = 0.1; // How much one press changes a volume in the settings menu
+pub const QUIT_HOLD_TIME: f32 = 1.0; // Seconds to hold Escape on the pause screen to quit
+pub const LEVEL_TRANSITION_DELAY: f32 = 1.5; // Seconds the "Level N!" text shows before the next level starts
+pub const LEVELS_DIR: &str = "assets/levels"; // Level files, replace the built in levels
+pub const CONFIG_FILE: &str = "assets/config.ron"; // Overrides the sizes and speeds below, see GameConfig
+pub const BINDINGS_FILE: &str = "bindings.ron"; // Key bindings, in the config directory, see KeyBindings
+pub const BALL_SIZE: f32 = 20.0;
+pub const BALL_START_SPEED: f32 = 400.0; // Speed the ball is served at on Normal difficulty
+pub const STARTING_LIVES: u32 = 3;
+pub const RESPAWN_DELAY: f32 = 1.0; // Seconds before a lost ball is replaced
+pub const PHYSICS_HZ: f64 = 120.0; // Fixed steps per second the ball is moved and collided in
+pub const LAUNCH_COUNTDOWN: f32 = 3.0; // Seconds a served ball waits on the paddle before it launches
+pub const MAX_LAUNCH_ANGLE: f32 = 1.0; // Radians either side of straight up a served ball can be aimed
+pub const AIM_SPEED: f32 = 1.5; // Radians per second A/D turn the aim
+pub const AIM_LENGTH: f32 = 60.0; // Length of the aiming line above the served ball
+pub const CORNER_TOLERANCE: f32 = 1.0; // Hits within this many pixels of a corner bounce off both faces
+pub const BALL_MAX_X_SPEED: f32 = 350.0; // Horizontal speed when the ball hits the very edge of the paddle
+pub const PADDLE_SPEED: f32 = 300.0; // Pixels per second the paddle moves
+pub const GAMEPAD_DEADZONE: f32 = 0.15; // Stick values smaller than this are ignored
+pub const POWERUP_CHANCE: f32 = 0.15; // Chance a broken block drops a power-up
+pub const POWERUP_SIZE: Vec2 = Vec2::new(40.0, 15.0);
+pub const POWERUP_SPEED: f32 = 200.0; // How fast power-ups fall
+pub const EXPAND_PADDLE_SCALE: f32 = 1.5; // Paddle width multiplier while expanded
+pub const SHRINK_PADDLE_SCALE: f32 = 0.5; // Paddle width multiplier while shrunk
+pub const PADDLE_EFFECT_DURATION: f32 = 15.0; // Seconds the paddle stays expanded or shrunk
+pub const PADDLE_RESIZE_SPEED: f32 = 200.0; // Pixels per second the paddle grows or shrinks towards its new width
+pub const SLOW_BALL_FACTOR: f32 = 0.6; // Ball speed multiplier while slowed
+pub const SLOW_BALL_DURATION: f32 = 8.0; // Seconds the balls stay slowed
+pub const BALL_SPEEDUP: f32 = 1.02; // Ball speed multiplier each time it breaks a block
+pub const MAX_BALL_SPEED: f32 = 800.0; // Nothing makes the ball faster than this
+pub const BALL_SLOW_COLOR: Color = Color::srgb(0.0, 1.0, 0.0); // Ball color at its starting speed
+pub const BALL_FAST_COLOR: Color = Color::srgb(1.0, 0.0, 0.0); // Ball color at MAX_BALL_SPEED
+pub const PADDLE_HIT_SPEEDUP: f32 = 1.03; // Ball speed multiplier each time it hits the paddle
+pub const SPIN_CURVE: f32 = 250.0; // Sideways pixels per second a ball at full spin gains each second
+pub const SPIN_DECAY: f32 = 0.4; // Fraction of its spin a ball still has after a second
+pub const MIN_AXIS_SPEED: f32 = 0.12; // Smallest share of the ball's speed along either axis
+pub const STALL_BAND_HEIGHT: f32 = 100.0; // Height of the bands the stall watchdog tracks the ball in
+pub const STALL_TIMEOUT: f32 = 5.0; // Seconds in one band before the ball gets kicked
+pub const STALL_KICK: f32 = 0.3; // Widest random turn a kick gives the ball, in radians
+pub const BALL_TIME_SPEEDUP: f32 = 2.0; // Pixels per second the ball speeds up for every second it's in play
+pub const MULTIBALL_SPREAD: f32 = std::f32::consts::PI / 6.0; // Radians either side of the ball its two copies leave at
+pub const STICKY_PADDLE_DURATION: f32 = 15.0; // Seconds the paddle catches the ball
+pub const LASER_DURATION: f32 = 10.0; // Seconds the paddle can shoot
+pub const LASER_COOLDOWN: f32 = 0.4; // Seconds between shots
+pub const BULLET_SIZE: Vec2 = Vec2::new(6.0, 15.0);
+pub const BULLET_SPEED: f32 = 600.0;
+pub const SHAKE_DURATION: f32 = 0.2; // Seconds the screen shakes after a block breaks
+pub const SHAKE_INTENSITY: f32 = 4.0; // Pixels the camera moves when a ball at serving speed breaks a block
+pub const BALL_LOST_SHAKE: f32 = 15.0; // Pixels the camera moves when a life is lost
+pub const HIT_PAUSE: f32 = 0.04; // Seconds the game freezes when a column of blocks is cleared
+pub const MAX_HIGH_SCORES: usize = 10; // Scores kept on the high score table
+pub const PARTICLES_PER_BLOCK: usize = 12; // Kept low so breaking many blocks at once stays cheap
+pub const PARTICLE_SIZE: f32 = 6.0;
+pub const PARTICLE_SPEED: f32 = 250.0; // Fastest a particle flies out
+pub const PARTICLE_LIFETIME: f32 = 0.5; // Seconds before a particle disappears
+pub const MOVING_BLOCK_SPEED: f32 = 80.0; // How fast moving blocks slide
+pub const MOVING_BLOCK_RANGE: f32 = 1.0; // Block widths a moving block goes at most either side of where it started
+pub const BLAST_RADIUS: f32 = 1.5; // Block widths, blocks with their center this close to an explosion break too
+pub const BLAST_LIFETIME: f32 = 0.3; // Seconds the explosion circle takes to grow
+pub const PARTICLE_GRAVITY: f32 = 600.0; // Downward acceleration of particles, in pixels per second squared
+pub const TRAIL_LENGTH: usize = 8; // Past positions drawn behind each ball, fewer is cheaper
+pub const COMBO_STEP: u32 = 3; // Blocks in a row it takes to double the points again
+pub const MAX_COMBO_MULTIPLIER: u32 = 16;
+pub const POPUP_LIFETIME: f32 = 0.5; // Seconds a "+1" stays over a broken block
+pub const POPUP_RISE_SPEED: f32 = 40.0; // Pixels per second popups float upwards
+
+// Registers everything the game needs, add it alongside DefaultPlugins
+pub struct BreakoutPlugin;
+
+impl Plugin for BreakoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Color::srgb(0.4, 0.4, 0.4))) // Set the background color
+            .init_state::<GameState>() // Initialize the game state
+            .insert_resource(Lives(STARTING_LIVES))
+            .init_resource::<Score>()
+            .init_resource::<Combo>()
+            .init_resource::<GameTimer>()
+            .init_resource::<ControlMode>()
+            .init_resource::<GameDimensions>() // main picks it from the command line
+            .init_resource::<GameConfig>() // Replaced by the config file on startup
+            .init_resource::<Difficulty>() // Follows the settings
+            .init_resource::<Settings>() // Replaced by the saved ones on startup
+            .init_resource::<SettingsRow>()
+            .init_resource::<KeyBindings>() // Replaced by the bindings file on startup
+            .init_resource::<GameRng>() // main picks it from the command line
+            .init_resource::<ScreenShake>()
+            .init_resource::<EscapeHold>()
+            .init_resource::<CurrentLevel>()
+            .add_event::<BlockDestroyedEvent>()
+            .add_event::<ExplosionEvent>()
+            .add_event::<BallBouncedEvent>()
+            .add_event::<BallLostEvent>()
+            .add_event::<GameWonEvent>()
+            .add_systems(Startup, (spawn_camera,
+                                   log_seed,
+                                   load_sounds,
+                                   load_high_scores,
+                                   load_settings,
+                                   load_key_bindings,
+                                   load_config,
+                                   load_levels)) // Startup runs once on launch
+            .add_systems(Update, menu_system.run_if(in_state(GameState::Menu))) // The board is spawned when leaving the menu
+            .add_systems(Update, ((player_movement.run_if(resource_equals(ControlMode::Keyboard)).after(pause_game),
+                                   mouse_movement.run_if(resource_equals(ControlMode::Mouse))).run_if(not(resource_exists::<LaunchAim>)), // A/D and the mouse aim instead
+                                  toggle_control_mode,
+                                  track_paddle_velocity.after(player_movement).after(mouse_movement),
+                                  (follow_paddle,
+                                   (aim_launch.run_if(resource_equals(ControlMode::Keyboard)),
+                                    aim_at_cursor.run_if(resource_equals(ControlMode::Mouse))).run_if(resource_exists::<LaunchAim>),
+                                   launch_ball.run_if(not(resource_exists::<LevelTransition>)),
+                                   (update_score,
+                                    drop_powerups,
+                                    spawn_particles,
+                                    hit_pause_on_column_clear)).chain(), // React to the hits of this frame's fixed steps
+                                  hit_pause_update.run_if(resource_exists::<HitPause>)
+                                      .before(hit_pause_on_column_clear), // A new pause doesn't lose the frame that started it
+                                  (fire_laser,
+                                   bullet_movement,
+                                   bullet_collision,
+                                   explode_blocks).chain().before(update_score), // Bullets and explosions score like ball hits
+                                  spawn_blasts.after(explode_blocks),
+                                  (powerup_movement,
+                                   powerup_collision,
+                                   powerup_expiry,
+                                   paddle_resize).chain(),
+                                  particle_update,
+                                  tick_game_timer.run_if(not(resource_exists::<LaunchCountdown>)), // The clock starts with the serve
+                                  blast_update,
+                                  popup_update,
+                                  render_trail,
+                                  game_win.run_if(not(resource_exists::<LevelTransition>)),
+                                  play_sounds.after(explode_blocks)
+                                      .after(game_win), // Sounds for everything above and the fixed steps
+                                  show_level_up_text.run_if(resource_added::<LevelTransition>),
+                                  level_transition.run_if(resource_exists::<LevelTransition>)).run_if(in_state(GameState::Playing))) // Only runs while playing
+            // The ball moves in fixed steps so hits don't depend on the frame rate, paused virtual time stops them too
+            .insert_resource(Time::<Fixed>::from_hz(PHYSICS_HZ))
+            .add_systems(FixedUpdate, ((move_blocks,
+                                        ball_movement,
+                                        ball_watchdog,
+                                        ball_collision,
+                                        block_collision).chain(), // Collisions check the path the ball just moved along
+                                       game_over.after(block_collision)).run_if(in_state(GameState::Playing)))
+            .add_systems(Update, pause_game.run_if(in_state(GameState::Playing).or(in_state(GameState::Paused)))
+                                           .run_if(not(resource_exists::<LevelTransition>))) // It would unpause the transition early
+            .add_systems(Update, pause_on_focus_lost.after(pause_game)
+                                                    .run_if(in_state(GameState::Playing))
+                                                    .run_if(not(resource_exists::<LevelTransition>)))
+            .add_systems(Update, (state_handler, // Handle game state changes
+                                  restart_game).run_if(in_state(GameState::GameOver).or(in_state(GameState::GameWin))))
+            .add_systems(OnEnter(GameState::Menu), show_menu_text)
+            .add_systems(Update, update_menu_text.run_if(in_state(GameState::Menu).and(resource_changed::<Settings>)))
+            .add_systems(OnEnter(GameState::Settings), show_settings_text)
+            // A new binding can't be taken from the Enter press that asked for it
+            .add_systems(Update, (settings_menu.run_if(not(resource_exists::<AwaitingKey>)),
+                                  rebind_key.run_if(resource_exists::<AwaitingKey>.and(not(resource_added::<AwaitingKey>))),
+                                  update_settings_text).chain().run_if(in_state(GameState::Settings)))
+            .add_systems(OnExit(GameState::Settings), despawn_all::<SettingsText>)
+            .add_systems(OnExit(GameState::Menu), despawn_all::<MenuText>)
+            .add_systems(Update, (pause_menu,
+                                  escape_key.after(pause_game),
+                                  update_pause_selection.run_if(resource_changed::<PauseOption>),
+                                  update_quit_progress.run_if(resource_changed::<EscapeHold>)).run_if(in_state(GameState::Paused)))
+            .add_systems(OnEnter(GameState::Paused), show_pause_text) // Trails stay where they are until play resumes
+            .add_systems(OnExit(GameState::Paused), (despawn_all::<PauseText>,
+                                                     reset_escape_hold))
+            .add_systems(OnEnter(GameState::GameOver), (show_game_over_text,
+                                                        record_high_score,
+                                                        despawn_all::<DespawnOnGameOver>).chain()) // Read the score before the board is cleared
+            .add_systems(OnExit(GameState::GameOver), despawn_all::<GameOverText>)
+            .add_systems(OnEnter(GameState::GameWin), (show_game_win_text,
+                                                       record_high_score,
+                                                       despawn_all::<DespawnOnGameOver>).chain())
+            .add_systems(OnExit(GameState::GameWin), despawn_all::<GameWinText>)
+            .add_systems(PreUpdate, toggle_fullscreen.after(InputSystem)) // Takes its Enter press before any menu sees it
+            .add_systems(Update, (resize_play_area,
+                                  (apply_settings,
+                                   save_settings).run_if(resource_changed::<Settings>),
+                                  respawn_ball,
+                                  (shake_on_ball_lost,
+                                   camera_shake).chain(),
+                                  update_score_display.run_if(resource_changed::<Score>),
+                                  update_combo_text.run_if(resource_changed::<Combo>),
+                                  update_lives_text.run_if(resource_changed::<Lives>),
+                                  update_level_text.run_if(resource_changed::<CurrentLevel>),
+                                  update_timer_text.run_if(resource_changed::<GameTimer>),
+                                  update_countdown_text,
+                                  interpolate_balls,
+                                  ball_color,
+                                  update_paddle_limits.after(resize_play_area).after(paddle_resize),
+                                  update_aim_indicator.after(follow_paddle))); // Update runs every frame
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2d); // Spawn a 2D camera
+}
+
+// Follow the window's size, moving the paddle and blocks so they keep their place against the edges.
+// The paddle and balls are pulled back inside straight away, even if the game is paused
+#[allow(clippy::type_complexity)]
+pub fn resize_play_area(mut events: EventReader<WindowResized>,
+                        mut dimensions: ResMut<GameDimensions>,
+                        config: Res<GameConfig>,
+                        mut player: Query<(&mut Transform, &PaddleWidth), With<Player>>,
+                        mut blocks: Query<&mut Transform, (With<Block>, Without<Player>)>,
+                        mut balls: Query<(&mut Transform, &mut PreviousPosition), (With<Ball>, Without<Player>, Without<Block>)>) {
+
+    let Some(resized) = events.read().last() else {
+        return;
+    };
+    let old = *dimensions;
+    dimensions.width = resized.width.max(MIN_WINDOW_WIDTH);
+    dimensions.height = resized.height.max(MIN_WINDOW_HEIGHT);
+
+    for (mut transform, width) in player.iter_mut() {
+        let max_x = paddle_max_x(width.0, &dimensions);
+        transform.translation.x = transform.translation.x.clamp(-max_x, max_x);
+        transform.translation.y = dimensions.player_y();
+    }
+    for mut transform in blocks.iter_mut() {
+        transform.translation.y += dimensions.top_row_y() - old.top_row_y();
+    }
+    let max_x = dimensions.width / 2.0 - config.ball_size / 2.0;
+    let max_y = dimensions.height / 2.0 - config.ball_size / 2.0;
+    for (mut transform, mut previous) in balls.iter_mut() {
+        transform.translation.x = transform.translation.x.clamp(-max_x, max_x);
+        transform.translation.y = transform.translation.y.min(max_y);
+        previous.0 = transform.translation.truncate(); // Being pushed in by the window isn't movement to sweep
+    }
+}
+
+// Size of the window before it went fullscreen, to go back to
+#[derive(Resource)]
+pub struct WindowedSize(pub Vec2);
+
+// F11 or Alt+Enter switch between a window and borderless fullscreen. The play area follows through the
+// resize events like any other resize, and the game state is left alone so a paused game stays paused
+pub fn toggle_fullscreen(mut commands: Commands,
+                         mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+                         mut window: Query<&mut Window, With<PrimaryWindow>>,
+                         windowed: Option<Res<WindowedSize>>) {
+
+    let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let alt_enter = alt && keyboard_input.clear_just_pressed(KeyCode::Enter); // Not also a menu choice
+    if !keyboard_input.just_pressed(KeyCode::F11) && !alt_enter {
+        return;
+    }
+    let Ok(mut window) = window.single_mut() else {
+        return;
+    };
+
+    if window.mode == WindowMode::Windowed {
+        commands.insert_resource(WindowedSize(window.resolution.size()));
+        window.mode = WindowMode::BorderlessFullscreen(MonitorSelection::Current);
+    } else {
+        window.mode = WindowMode::Windowed;
+        if let Some(windowed) = windowed {
+            window.resolution.set(windowed.0.x, windowed.0.y);
+        }
+        window.position = WindowPosition::Centered(MonitorSelection::Current);
+    }
+}
+
+fn log_seed(rng: Res<GameRng>) {
+    info!("Random seed: {}", rng.seed); // Enough to replay this run
+}
+
+pub fn spawn_map(mut commands: Commands,
+                 mut mesh_assets: ResMut<Assets<Mesh>>,
+                 mut material_assets: ResMut<Assets<ColorMaterial>>,
+                 dimensions: Res<GameDimensions>,
+                 config: Res<GameConfig>,
+                 difficulty: Res<Difficulty>) {
+
+    // Create a rectangle mesh to represent the player
+    let player_mesh = mesh_assets.add(Rectangle::new(PLAYER_SIZE, PLAYER_WIDTH));
+    let player_material = material_assets.add(Color::srgb(1.0, 0.0, 0.0));
+
+    // Create a ball that bounces between player and blocks
+    let ball_mesh = mesh_assets.add(Circle::new(config.ball_size / 2.0)); // Takes a radius, ball_size is the diameter the collisions use
+    let ball_material = material_assets.add(BALL_SLOW_COLOR);
+
+    // Spawn the player at the bottom of the window
+    commands.spawn((
+        Player,
+        DespawnOnGameOver, // This component will be used to despawn the player on game over
+        PaddleWidth(difficulty.paddle_size(&config)),
+        Transform::from_xyz(0.0, dimensions.player_y(), 0.0)
+            .with_scale(Vec3::new(difficulty.paddle_size(&config) / PLAYER_SIZE, 1.0, 1.0)), // The mesh is PLAYER_SIZE wide
+        Mesh2d(player_mesh.clone()),
+        MeshMaterial2d(player_material),
+    ));
+
+    // Show how far the paddle can go, update_paddle_limits puts them in place
+    let limit_material = material_assets.add(Color::srgba(1.0, 1.0, 1.0, 0.12));
+    for side in [-1.0, 1.0] {
+        commands.spawn((
+            PaddleLimit(side),
+            DespawnOnGameOver,
+            Transform::default(),
+            Visibility::Hidden, // Until it's been placed
+            Mesh2d(player_mesh.clone()),
+            MeshMaterial2d(limit_material.clone()),
+        ));
+    }
+
+    // Keep the ball handles around so lost balls can be respawned
+    let ball_assets = BallAssets {
+        mesh: ball_mesh.clone(),
+        material: ball_material,
+    };
+    spawn_ball(&mut commands, &ball_assets, &dimensions, &config, difficulty.ball_speed(&config));
+    commands.insert_resource(ball_assets);
+
+    commands.insert_resource(PowerUpAssets {
+        mesh: mesh_assets.add(Rectangle::new(POWERUP_SIZE.x, POWERUP_SIZE.y)),
+        materials: vec![
+            material_assets.add(Color::srgb(1.0, 0.8, 0.0)),
+            material_assets.add(Color::srgb(0.6, 0.0, 0.8)),
+            material_assets.add(Color::srgb(0.0, 0.8, 1.0)),
+            material_assets.add(Color::srgb(1.0, 1.0, 1.0)),
+            material_assets.add(Color::srgb(1.0, 0.4, 0.7)),
+            material_assets.add(Color::srgb(1.0, 0.3, 0.0)),
+        ],
+    });
+    commands.insert_resource(TrailAssets {
+        mesh: ball_mesh.clone(),
+        // Faintest for the oldest position
+        materials: (0..TRAIL_LENGTH)
+            .map(|age| material_assets.add(Color::srgba(0.0, 1.0, 0.0, 0.4 * (1.0 - age as f32 / TRAIL_LENGTH as f32))))
+            .collect(),
+    });
+    commands.insert_resource(ParticleAssets {
+        mesh: mesh_assets.add(Rectangle::new(PARTICLE_SIZE, PARTICLE_SIZE)),
+        blast_mesh: mesh_assets.add(Circle::new(1.0)),
+        blast_material: material_assets.add(Color::srgba(1.0, 0.6, 0.1, 0.5)),
+    });
+    commands.insert_resource(BulletAssets {
+        mesh: mesh_assets.add(Rectangle::new(BULLET_SIZE.x, BULLET_SIZE.y)),
+        material: material_assets.add(Color::srgb(1.0, 0.3, 0.0)),
+    });
+
+    // The HUD is a UI row across the top of the window, laid out by flexbox so it follows any window size.
+    // Lives on the left, level and time in the middle, score on the right
+    let hud_font = TextFont {
+        font_size: 20.0,
+        ..default()
+    };
+    commands.spawn((
+        Hud,
+        DespawnOnGameOver, // This component will be used to despawn the HUD on game over, its text goes with it
+        Node {
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::FlexStart,
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        children![
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                children![
+                    (LivesText, Text::new(format!("Lives: {}", difficulty.lives())), hud_font.clone()),
+                    (DifficultyText, Text::new(format!("Difficulty: {}", *difficulty)), hud_font.clone()),
+                ],
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (LevelText, Text::new("Level: 1"), hud_font.clone()),
+                    (TimerText, Text::new("Time: 00:00"), hud_font.clone()),
+                ],
+            ),
+            (
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                },
+                children![
+                    (ScoreText, Text::new("Score: 0"), hud_font.clone()),
+                    (ComboText, Text::new(""), hud_font), // Empty until a combo starts
+                ],
+            ),
+        ],
+    ));
+}
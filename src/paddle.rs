@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::settings::{KeyBindings, Settings};
+use crate::*;
+
+#[derive(Component)]
+#[require(PaddleWidth, PaddleVelocity)]
+pub struct Player; // Represents the player entity
+
+#[derive(Component)]
+pub struct PaddleWidth(pub f32); // Current width of the paddle, power-ups can change it
+
+impl Default for PaddleWidth {
+    fn default() -> Self {
+        PaddleWidth(PLAYER_SIZE)
+    }
+}
+
+#[derive(Component, Default)]
+pub struct PaddleVelocity {
+    pub x: f32, // Pixels per second over the last frame, positive is to the right
+    pub last_x: f32,
+}
+
+#[derive(Component)]
+pub struct PaddleLimit(pub f32); // Faint copy of the paddle at the furthest it can go, -1.0 for the left side and 1.0 for the right
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub enum ControlMode {
+    #[default]
+    Keyboard, // Move with A/D
+    Mouse, // Paddle follows the cursor
+}
+
+pub fn player_movement(mut pos: Query<(&mut Transform, &PaddleWidth), With<Player>>,
+                       dimensions: Res<GameDimensions>,
+                       config: Res<GameConfig>,
+                       time: Res<Time<Virtual>>,
+                       keyboard_input: Res<ButtonInput<KeyCode>>,
+                       bindings: Res<KeyBindings>,
+                       gamepads: Query<&Gamepad>) {
+
+    if time.is_paused() {
+        return; // Don't move on the frame the game gets paused
+    }
+    let step = config.paddle_speed * time.delta_secs();
+
+    // Left stick x, ignoring small values so stick drift doesn't move the paddle.
+    // Gamepads are looked up every frame, so plugging one in or out mid-game just works
+    let stick = gamepads.iter()
+        .filter_map(|gamepad| gamepad.get(GamepadAxis::LeftStickX))
+        .find(|x| x.abs() > GAMEPAD_DEADZONE)
+        .unwrap_or(0.0);
+    let dpad_left = gamepads.iter().any(|gamepad| gamepad.pressed(GamepadButton::DPadLeft));
+    let dpad_right = gamepads.iter().any(|gamepad| gamepad.pressed(GamepadButton::DPadRight));
+
+    for (mut transform, width) in pos.iter_mut() {
+        // The arrow keys always work too, whatever the movement keys are bound to
+        if keyboard_input.any_pressed([bindings.move_left, KeyCode::ArrowLeft]) || dpad_left {
+            transform.translation.x -= step; // Move left
+        }
+        if keyboard_input.any_pressed([bindings.move_right, KeyCode::ArrowRight]) || dpad_right {
+            transform.translation.x += step; // Move right
+        }
+        transform.translation.x += stick * step; // Tilting the stick further moves faster
+
+        // Stop with the paddle's edge right against the wall
+        let max_x = paddle_max_x(width.0, &dimensions);
+        transform.translation.x = transform.translation.x.clamp(-max_x, max_x);
+    }
+}
+
+// Move the paddle to the cursor's x position
+pub fn mouse_movement(mut pos: Query<(&mut Transform, &PaddleWidth), With<Player>>,
+                      dimensions: Res<GameDimensions>,
+                      window: Query<&Window, With<PrimaryWindow>>,
+                      camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>) {
+
+    let (Ok(window), Ok((camera, camera_tf))) = (window.single(), camera.single()) else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return; // Cursor is outside the window
+    };
+    let Ok(cursor) = camera.viewport_to_world_2d(camera_tf, cursor) else {
+        return;
+    };
+
+    for (mut transform, width) in pos.iter_mut() {
+        let max_x = paddle_max_x(width.0, &dimensions);
+        transform.translation.x = cursor.x.clamp(-max_x, max_x);
+    }
+}
+
+// Measure how fast the paddle moved this frame whatever moved it, a hit ball takes spin from it
+pub fn track_paddle_velocity(mut player: Query<(&Transform, &mut PaddleVelocity), With<Player>>,
+                             time: Res<Time>) {
+
+    if time.delta_secs() == 0.0 {
+        return; // Paused, keep the last speed for when the game goes on
+    }
+    for (transform, mut velocity) in player.iter_mut() {
+        velocity.x = (transform.translation.x - velocity.last_x) / time.delta_secs();
+        velocity.last_x = transform.translation.x;
+    }
+}
+
+// Furthest the paddle's center can move from the middle, with its edge right against the wall
+pub fn paddle_max_x(width: f32, dimensions: &GameDimensions) -> f32 {
+    dimensions.width / 2.0 - width / 2.0
+}
+
+// Keep the limit markers where the paddle stops, following its width and the window's size
+pub fn update_paddle_limits(player: Query<&PaddleWidth, With<Player>>,
+                            mut limits: Query<(&PaddleLimit, &mut Transform, &mut Visibility), Without<Player>>,
+                            dimensions: Res<GameDimensions>,
+                            settings: Res<Settings>) {
+
+    let Ok(width) = player.single() else {
+        return;
+    };
+    let max_x = paddle_max_x(width.0, &dimensions);
+    for (limit, mut transform, mut visibility) in limits.iter_mut() {
+        transform.translation = Vec3::new(limit.0 * max_x, dimensions.player_y(), -1.0); // Behind everything that moves
+        transform.scale.x = width.0 / PLAYER_SIZE; // Same mesh size as the paddle
+        visibility.set_if_neq(if settings.show_paddle_limits { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
+
+// Switch between keyboard and mouse paddle control with M, it's remembered in the settings
+pub fn toggle_control_mode(mut settings: ResMut<Settings>,
+                           keyboard_input: Res<ButtonInput<KeyCode>>) {
+
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        settings.control_mode = match settings.control_mode {
+            ControlMode::Keyboard => ControlMode::Mouse,
+            ControlMode::Mouse => ControlMode::Keyboard,
+        };
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ball::{spawn_ball_at, Ball, BallAssets, BallSpeed};
+use crate::collision::aabb_overlap;
+use crate::config::GameConfig;
+use crate::blocks::BlockDestroyedEvent;
+use crate::laser::LaserPaddle;
+use crate::paddle::{paddle_max_x, PaddleWidth, Player};
+use crate::state::Difficulty;
+use crate::*;
+
+#[derive(Component)]
+#[require(Velocity)]
+pub struct PowerUp(pub PowerUpKind); // Falls towards the paddle, applies its effect when caught
+
+#[derive(Clone, Copy)]
+pub enum PowerUpKind {
+    ExpandPaddle, // Makes the paddle wider
+    ShrinkPaddle, // Makes the paddle narrower, a penalty
+    SlowBall, // Slows every ball down
+    MultiBall, // Splits a ball into three
+    Sticky, // The paddle catches the ball
+    Laser, // The paddle shoots bullets that break blocks
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 6] = [
+        PowerUpKind::ExpandPaddle,
+        PowerUpKind::ShrinkPaddle,
+        PowerUpKind::SlowBall,
+        PowerUpKind::MultiBall,
+        PowerUpKind::Sticky,
+        PowerUpKind::Laser,
+    ];
+}
+
+#[derive(Component)]
+pub struct PaddleSizeEffect {
+    pub timer: Timer, // Counts down until the paddle goes back to normal
+    pub scale: f32, // Width multiplier while the effect lasts
+}
+
+#[derive(Component)]
+pub struct StickyPaddle(pub Timer); // Counts down until the paddle stops catching the ball
+
+#[derive(Component)]
+pub struct SlowBall {
+    pub timer: Timer, // Counts down until the ball speeds back up
+}
+
+#[derive(Resource)]
+pub struct PowerUpAssets {
+    pub mesh: Handle<Mesh>,
+    pub materials: Vec<Handle<ColorMaterial>>, // One material per PowerUpKind, in declaration order
+}
+
+// Spawn a power-up that falls from where a block was broken
+pub fn spawn_powerup(commands: &mut Commands, assets: &PowerUpAssets, position: Vec3, kind: PowerUpKind) {
+    commands.spawn((
+        PowerUp(kind),
+        DespawnOnGameOver,
+        Transform::from_translation(position),
+        Velocity(Vec2::new(0.0, -POWERUP_SPEED)),
+        Mesh2d(assets.mesh.clone()),
+        MeshMaterial2d(assets.materials[kind as usize].clone()),
+    ));
+}
+
+// Give each broken block a chance to drop a power-up
+pub fn drop_powerups(mut commands: Commands,
+                     mut events: EventReader<BlockDestroyedEvent>,
+                     mut rng: ResMut<GameRng>,
+                     assets: Res<PowerUpAssets>) {
+
+    for event in events.read() {
+        if rng.rng.gen_bool(POWERUP_CHANCE as f64) {
+            let kind = PowerUpKind::ALL[rng.rng.gen_range(0..PowerUpKind::ALL.len())];
+            spawn_powerup(&mut commands, &assets, event.position, kind);
+        }
+    }
+}
+
+pub fn powerup_movement(mut commands: Commands,
+                        mut powerups: Query<(Entity, &mut Transform, &Velocity), With<PowerUp>>,
+                        dimensions: Res<GameDimensions>,
+                        time: Res<Time>) {
+
+    for (entity, mut transform, vel) in powerups.iter_mut() {
+        transform.translation.x += vel.0.x * time.delta_secs();
+        transform.translation.y += vel.0.y * time.delta_secs();
+
+        if transform.translation.y < -dimensions.height / 2.0 - POWERUP_SIZE.y {
+            commands.entity(entity).despawn(); // Missed, fell off the bottom
+        }
+    }
+}
+
+// Apply a power-up's effect when it lands on the paddle
+#[allow(clippy::type_complexity)]
+pub fn powerup_collision(mut commands: Commands,
+                         powerups: Query<(Entity, &Transform, &PowerUp), Without<Player>>,
+                         player: Query<(Entity, &Transform, &PaddleWidth), With<Player>>,
+                         mut balls: Query<(Entity, &Transform, &Velocity, &mut BallSpeed, Option<&mut SlowBall>), With<Ball>>,
+                         ball_assets: Res<BallAssets>) {
+
+    let Ok((player_entity, player_tf, width)) = player.single() else {
+        return;
+    };
+
+    for (entity, powerup_tf, powerup) in powerups.iter() {
+        if !aabb_overlap(powerup_tf.translation.truncate(),
+                         POWERUP_SIZE / 2.0,
+                         player_tf.translation.truncate(),
+                         Vec2::new(width.0, PLAYER_WIDTH) / 2.0) {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        match powerup.0 {
+            PowerUpKind::ExpandPaddle | PowerUpKind::ShrinkPaddle => {
+                let scale = match powerup.0 {
+                    PowerUpKind::ExpandPaddle => EXPAND_PADDLE_SCALE,
+                    _ => SHRINK_PADDLE_SCALE,
+                };
+                // Replaces a running effect, so catching more refreshes the timer instead of stacking
+                commands.entity(player_entity).insert(PaddleSizeEffect {
+                    timer: Timer::from_seconds(PADDLE_EFFECT_DURATION, TimerMode::Once),
+                    scale,
+                });
+            }
+            PowerUpKind::SlowBall => {
+                for (ball_entity, _, _, mut speed, slow) in balls.iter_mut() {
+                    if let Some(mut slow) = slow {
+                        slow.timer.reset(); // Already slowed, just make it last longer
+                        continue;
+                    }
+                    commands.entity(ball_entity).insert(SlowBall {
+                        timer: Timer::from_seconds(SLOW_BALL_DURATION, TimerMode::Once),
+                    });
+                    speed.0 *= SLOW_BALL_FACTOR;
+                }
+            }
+            PowerUpKind::Sticky => {
+                commands.entity(player_entity)
+                    .insert(StickyPaddle(Timer::from_seconds(STICKY_PADDLE_DURATION, TimerMode::Once)));
+            }
+            PowerUpKind::Laser => {
+                commands.entity(player_entity).insert(LaserPaddle {
+                    timer: Timer::from_seconds(LASER_DURATION, TimerMode::Once),
+                    cooldown: Timer::from_seconds(0.0, TimerMode::Once), // Can shoot straight away
+                });
+            }
+            PowerUpKind::MultiBall => {
+                // Fan two new balls out either side of one that's in play
+                if let Some((_, ball_tf, vel, speed, slow)) = balls.iter().next() {
+                    let direction = vel.0.try_normalize().unwrap_or(Vec2::Y); // A ball on the paddle isn't moving yet
+                    for angle in [-MULTIBALL_SPREAD, MULTIBALL_SPREAD] {
+                        let copy = spawn_ball_at(&mut commands,
+                                                 &ball_assets,
+                                                 ball_tf.translation.truncate(),
+                                                 Vec2::from_angle(angle).rotate(direction) * speed.0);
+                        // Copies of a slowed ball are slowed too, and speed back up with it
+                        if let Some(slow) = slow {
+                            commands.entity(copy).insert(SlowBall { timer: slow.timer.clone() });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Undo power-up effects once they wear off
+pub fn powerup_expiry(mut commands: Commands,
+                      mut player: Query<(Entity, &mut PaddleSizeEffect)>,
+                      mut sticky: Query<(Entity, &mut StickyPaddle)>,
+                      mut laser: Query<(Entity, &mut LaserPaddle)>,
+                      mut balls: Query<(Entity, &mut BallSpeed, &mut SlowBall)>,
+                      time: Res<Time>) {
+
+    for (entity, mut effect) in player.iter_mut() {
+        if effect.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<PaddleSizeEffect>(); // paddle_resize eases it back to normal
+        }
+    }
+
+    for (entity, mut timer) in sticky.iter_mut() {
+        if timer.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<StickyPaddle>(); // Balls already caught can still be launched
+        }
+    }
+
+    for (entity, mut laser) in laser.iter_mut() {
+        if laser.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<LaserPaddle>(); // Bullets already fired keep going
+        }
+    }
+
+    for (entity, mut speed, mut slow) in balls.iter_mut() {
+        if slow.timer.tick(time.delta()).finished() {
+            speed.0 = (speed.0 / SLOW_BALL_FACTOR).min(MAX_BALL_SPEED); // Keeps any speed up it got while slowed
+            commands.entity(entity).remove::<SlowBall>();
+        }
+    }
+}
+
+// Grow or shrink the paddle towards the width its current effect wants
+pub fn paddle_resize(mut player: Query<(&mut Transform, &mut PaddleWidth, Option<&PaddleSizeEffect>), With<Player>>,
+                     difficulty: Res<Difficulty>,
+                     dimensions: Res<GameDimensions>,
+                     config: Res<GameConfig>,
+                     time: Res<Time>) {
+
+    for (mut transform, mut width, effect) in player.iter_mut() {
+        let target = difficulty.paddle_size(&config) * effect.map_or(1.0, |effect| effect.scale);
+        let step = PADDLE_RESIZE_SPEED * time.delta_secs();
+        width.0 += (target - width.0).clamp(-step, step);
+        transform.scale.x = width.0 / PLAYER_SIZE; // The mesh is PLAYER_SIZE wide
+
+        // Growing next to a wall would push the paddle's edge off screen
+        let max_x = paddle_max_x(width.0, &dimensions);
+        transform.translation.x = transform.translation.x.clamp(-max_x, max_x);
+    }
+}
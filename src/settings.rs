@@ -0,0 +1,408 @@
+use std::fs;
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, DynamicVariant, Enum, TypeInfo, Typed, VariantInfo};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::paddle::ControlMode;
+use crate::state::{Difficulty, GameState};
+use crate::*;
+
+// Everything the player can change, saved whenever it changes
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)] // Settings missing from an older file keep their defaults
+pub struct Settings {
+    pub music_volume: f32, // 0.0 is silent and 1.0 is full volume. Kept for when there's music, the menu doesn't show it yet
+    pub sfx_volume: f32,
+    pub muted: bool, // Silences everything without losing the volumes
+    pub difficulty: Difficulty, // Used for the next game started
+    pub control_mode: ControlMode,
+    pub reduce_motion: bool, // No screen shake or hit pauses
+    pub show_paddle_limits: bool, // Markers at the furthest the paddle goes, for new players
+}
+
+// Keyboard keys for the actions players most often want somewhere else, like on an AZERTY keyboard.
+// Kept in BINDINGS_FILE in the config directory rather than with the settings, so it's easy to find and edit
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct KeyBindings {
+    #[serde(with = "key_name")]
+    pub move_left: KeyCode, // Also turns the serve's aim left
+    #[serde(with = "key_name")]
+    pub move_right: KeyCode,
+    #[serde(with = "key_name")]
+    pub pause: KeyCode,
+    #[serde(with = "key_name")]
+    pub serve: KeyCode, // Launches a waiting ball, fires the laser otherwise
+    #[serde(with = "key_name")]
+    pub restart: KeyCode, // On the game over and win screens
+    #[serde(with = "key_name")]
+    pub quit: KeyCode, // Back to the menu from the pause screen
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            move_left: KeyCode::KeyA,
+            move_right: KeyCode::KeyD,
+            pause: KeyCode::Space,
+            serve: KeyCode::KeyW,
+            restart: KeyCode::KeyR,
+            quit: KeyCode::KeyQ,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).unwrap_or_default()
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    // Every action with the name the settings menu gives it
+    pub fn actions(&self) -> [(&'static str, KeyCode); 6] {
+        [
+            ("Move Left", self.move_left),
+            ("Move Right", self.move_right),
+            ("Pause", self.pause),
+            ("Serve", self.serve),
+            ("Restart", self.restart),
+            ("Quit", self.quit),
+        ]
+    }
+
+    // Pairs of actions sharing a key, whichever system reads it first wins so it's worth a warning
+    pub fn conflicts(&self) -> Vec<(&'static str, &'static str, KeyCode)> {
+        let actions = self.actions();
+        let mut conflicts = Vec::new();
+        for (i, (first, key)) in actions.iter().enumerate() {
+            for (second, other) in &actions[i + 1..] {
+                if key == other {
+                    conflicts.push((*first, *second, *key));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+// KeyCode only implements serde with Bevy's serialize feature, so keys are saved by name through reflection
+mod key_name {
+    use super::*;
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(key.variant_name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        // from_reflect panics on a variant KeyCode doesn't have, so check the name first
+        let TypeInfo::Enum(info) = KeyCode::type_info() else {
+            return Err(D::Error::custom("KeyCode isn't an enum"));
+        };
+        if !matches!(info.variant(&name), Some(VariantInfo::Unit(_))) {
+            return Err(D::Error::custom(format!("unknown key {name}")));
+        }
+        KeyCode::from_reflect(&DynamicEnum::new(name.as_str(), DynamicVariant::Unit))
+            .ok_or_else(|| D::Error::custom(format!("unknown key {name}")))
+    }
+}
+
+// Short name for a key, "A" rather than "KeyA"
+pub fn key_label(key: KeyCode) -> String {
+    let name = key.variant_name();
+    name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")).unwrap_or(name).to_string()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            muted: false,
+            difficulty: Difficulty::Normal,
+            control_mode: ControlMode::Keyboard,
+            reduce_motion: false,
+            show_paddle_limits: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    // A corrupt file gives the defaults instead of an error
+    pub fn from_json(json: &str) -> Self {
+        let mut settings: Settings = serde_json::from_str(json).unwrap_or_default();
+        settings.music_volume = settings.music_volume.clamp(0.0, 1.0); // Don't trust a hand edited file
+        settings.sfx_volume = settings.sfx_volume.clamp(0.0, 1.0);
+        settings
+    }
+
+    // Volume sound effects play at
+    pub fn effective_sfx_volume(&self) -> f32 {
+        if self.muted { 0.0 } else { self.sfx_volume }
+    }
+}
+
+// Lines of the settings menu, in the order they're shown
+#[derive(Resource, Clone, Copy, Default, PartialEq)]
+pub enum SettingsRow {
+    #[default]
+    SfxVolume,
+    Muted,
+    Difficulty,
+    Controls,
+    ReduceMotion,
+    PaddleLimits,
+    MoveLeft,
+    MoveRight,
+    Pause,
+    Serve,
+    Restart,
+    Quit,
+}
+
+impl SettingsRow {
+    pub const ALL: [SettingsRow; 12] = [
+        SettingsRow::SfxVolume,
+        SettingsRow::Muted,
+        SettingsRow::Difficulty,
+        SettingsRow::Controls,
+        SettingsRow::ReduceMotion,
+        SettingsRow::PaddleLimits,
+        SettingsRow::MoveLeft,
+        SettingsRow::MoveRight,
+        SettingsRow::Pause,
+        SettingsRow::Serve,
+        SettingsRow::Restart,
+        SettingsRow::Quit,
+    ];
+
+    fn is_key(self) -> bool {
+        matches!(self, SettingsRow::MoveLeft | SettingsRow::MoveRight | SettingsRow::Pause
+            | SettingsRow::Serve | SettingsRow::Restart | SettingsRow::Quit)
+    }
+
+    // The binding this row changes, if it's one of the key rows
+    fn key<'a>(&self, bindings: &'a mut KeyBindings) -> Option<&'a mut KeyCode> {
+        match self {
+            SettingsRow::MoveLeft => Some(&mut bindings.move_left),
+            SettingsRow::MoveRight => Some(&mut bindings.move_right),
+            SettingsRow::Pause => Some(&mut bindings.pause),
+            SettingsRow::Serve => Some(&mut bindings.serve),
+            SettingsRow::Restart => Some(&mut bindings.restart),
+            SettingsRow::Quit => Some(&mut bindings.quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct AwaitingKey; // The selected key row takes the next key pressed
+
+#[derive(Component)]
+pub struct SettingsText;
+
+// Read the settings file, writing the defaults if there isn't one yet. `--difficulty` on the command line
+// picks the difficulty like the menu would
+pub fn load_settings(mut commands: Commands) {
+    let mut settings = data_file("settings.json")
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|json| Settings::from_json(&json))
+        .unwrap_or_default();
+    if let Some(difficulty) = Difficulty::from_args(std::env::args()) {
+        settings.difficulty = difficulty;
+    }
+    commands.insert_resource(settings); // Counts as a change, so save_settings writes it out
+}
+
+pub fn save_settings(settings: Res<Settings>) {
+    let Some(path) = data_file("settings.json") else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(err) = fs::write(&path, settings.to_json()) {
+        warn!("Couldn't save settings to {}: {err}", path.display());
+    }
+}
+
+// Read the bindings file. The defaults are written out on first run so players can find it,
+// a broken file is reported and left alone for them to fix
+pub fn load_key_bindings(mut commands: Commands) {
+    let path = config_file(BINDINGS_FILE);
+    let bindings = match path.as_ref().map(fs::read_to_string) {
+        Some(Ok(text)) => KeyBindings::from_ron(&text).unwrap_or_else(|err| {
+            error!("Ignoring {BINDINGS_FILE}: {err}");
+            KeyBindings::default()
+        }),
+        Some(Err(_)) => {
+            save_key_bindings(&KeyBindings::default());
+            KeyBindings::default()
+        }
+        None => KeyBindings::default(),
+    };
+    warn_about_conflicts(&bindings);
+    commands.insert_resource(bindings);
+}
+
+pub fn save_key_bindings(bindings: &KeyBindings) {
+    let Some(path) = config_file(BINDINGS_FILE) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(err) = fs::write(&path, bindings.to_ron()) {
+        warn!("Couldn't save key bindings to {}: {err}", path.display());
+    }
+}
+
+fn warn_about_conflicts(bindings: &KeyBindings) {
+    for (first, second, key) in bindings.conflicts() {
+        warn!("{first} and {second} are both bound to {}, only one of them will work", key_label(key));
+    }
+}
+
+// Copy the settings into the resources the game systems run on
+pub fn apply_settings(settings: Res<Settings>,
+                      mut difficulty: ResMut<Difficulty>,
+                      mut mode: ResMut<ControlMode>) {
+
+    difficulty.set_if_neq(settings.difficulty);
+    mode.set_if_neq(settings.control_mode);
+}
+
+// Up/Down pick a line, Left/Right change it, Enter on a key line waits for the new key,
+// Escape or Enter anywhere else go back to the main menu
+pub fn settings_menu(mut commands: Commands,
+                     mut settings: ResMut<Settings>,
+                     mut row: ResMut<SettingsRow>,
+                     mut next_state: ResMut<NextState<GameState>>,
+                     keyboard_input: Res<ButtonInput<KeyCode>>) {
+
+    let index = SettingsRow::ALL.iter().position(|r| r == &*row).unwrap_or(0);
+    if keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        *row = SettingsRow::ALL[(index + SettingsRow::ALL.len() - 1) % SettingsRow::ALL.len()];
+    } else if keyboard_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        *row = SettingsRow::ALL[(index + 1) % SettingsRow::ALL.len()];
+    }
+
+    let step = if keyboard_input.any_just_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+        -1.0
+    } else if keyboard_input.any_just_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+        1.0
+    } else {
+        0.0
+    };
+    if step != 0.0 {
+        match *row {
+            SettingsRow::SfxVolume => settings.sfx_volume = (settings.sfx_volume + step * VOLUME_STEP).clamp(0.0, 1.0),
+            SettingsRow::Muted => settings.muted = !settings.muted,
+            SettingsRow::Difficulty => {
+                settings.difficulty = match (settings.difficulty, step > 0.0) {
+                    (Difficulty::Easy, true) | (Difficulty::Hard, false) => Difficulty::Normal,
+                    (Difficulty::Normal, true) => Difficulty::Hard,
+                    (Difficulty::Normal, false) => Difficulty::Easy,
+                    (difficulty, _) => difficulty, // Already at the end
+                };
+            }
+            SettingsRow::Controls => {
+                settings.control_mode = match settings.control_mode {
+                    ControlMode::Keyboard => ControlMode::Mouse,
+                    ControlMode::Mouse => ControlMode::Keyboard,
+                };
+            }
+            SettingsRow::ReduceMotion => settings.reduce_motion = !settings.reduce_motion,
+            SettingsRow::PaddleLimits => settings.show_paddle_limits = !settings.show_paddle_limits,
+            SettingsRow::MoveLeft | SettingsRow::MoveRight | SettingsRow::Pause
+            | SettingsRow::Serve | SettingsRow::Restart | SettingsRow::Quit => {} // Changed with Enter
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Enter) && row.is_key() {
+        commands.insert_resource(AwaitingKey);
+    } else if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::Enter]) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+// Bind the first key pressed to the selected row, Escape keeps the old one
+pub fn rebind_key(mut commands: Commands,
+                  mut bindings: ResMut<KeyBindings>,
+                  row: Res<SettingsRow>,
+                  keyboard_input: Res<ButtonInput<KeyCode>>) {
+
+    let Some(&key) = keyboard_input.get_just_pressed().next() else {
+        return;
+    };
+    if key != KeyCode::Escape
+        && let Some(binding) = row.key(&mut bindings) {
+        *binding = key;
+        warn_about_conflicts(&bindings);
+        save_key_bindings(&bindings);
+    }
+    commands.remove_resource::<AwaitingKey>();
+}
+
+pub fn show_settings_text(mut commands: Commands,
+                          settings: Res<Settings>,
+                          bindings: Res<KeyBindings>,
+                          row: Res<SettingsRow>) {
+
+    commands.spawn((
+        SettingsText,
+        Text2d::new(settings_text(&settings, &bindings, *row, false)),
+        TextFont {
+            font_size: 25.0, // Small enough for every row on the shortest window
+            ..default()
+        },
+    ));
+}
+
+pub fn update_settings_text(settings: Res<Settings>,
+                            bindings: Res<KeyBindings>,
+                            row: Res<SettingsRow>,
+                            awaiting: Option<Res<AwaitingKey>>,
+                            mut text: Query<&mut Text2d, With<SettingsText>>) {
+
+    let new_text = settings_text(&settings, &bindings, *row, awaiting.is_some());
+    if let Ok(mut text) = text.single_mut()
+        && text.0 != new_text {
+        text.0 = new_text;
+    }
+}
+
+fn settings_text(settings: &Settings, bindings: &KeyBindings, selected: SettingsRow, awaiting_key: bool) -> String {
+    let lines = SettingsRow::ALL.map(|row| {
+        let line = match row {
+            SettingsRow::SfxVolume => format!("Sound: {:.0}%", settings.sfx_volume * 100.0),
+            SettingsRow::Muted => format!("Muted: {}", if settings.muted { "Yes" } else { "No" }),
+            SettingsRow::Difficulty => format!("Difficulty: {}", settings.difficulty),
+            SettingsRow::Controls => format!("Controls: {:?}", settings.control_mode),
+            SettingsRow::ReduceMotion => format!("Reduce Motion: {}", if settings.reduce_motion { "Yes" } else { "No" }),
+            SettingsRow::PaddleLimits => format!("Paddle Limits: {}", if settings.show_paddle_limits { "Shown" } else { "Hidden" }),
+            SettingsRow::MoveLeft => format!("Move Left: {}", key_label(bindings.move_left)),
+            SettingsRow::MoveRight => format!("Move Right: {}", key_label(bindings.move_right)),
+            SettingsRow::Pause => format!("Pause: {}", key_label(bindings.pause)),
+            SettingsRow::Serve => format!("Serve/Fire: {}", key_label(bindings.serve)),
+            SettingsRow::Restart => format!("Restart: {}", key_label(bindings.restart)),
+            SettingsRow::Quit => format!("Quit: {}", key_label(bindings.quit)),
+        };
+        if row == selected && awaiting_key {
+            String::from("> Press a key, Esc to cancel <")
+        } else if row == selected {
+            format!("> {} <", line)
+        } else {
+            line
+        }
+    });
+    format!("Settings\n\n{}\n\nLeft/Right change   Enter rebind   Esc back", lines.join("\n"))
+}
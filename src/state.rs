@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use serde::{Deserialize, Serialize};
+
+use crate::ball::{spawn_ball, Ball, BallAssets, LaunchAim, LaunchCountdown, StuckToPaddle};
+use crate::blocks::{spawn_blocks, Block, Indestructible};
+use crate::config::GameConfig;
+use crate::laser::Bullet;
+use crate::levels::{CurrentLevel, LevelData};
+use crate::effects::{HitPause, Particle};
+use crate::powerup::PowerUp;
+use crate::settings::{KeyBindings, Settings};
+use crate::ui::{Combo, GameTimer, LevelUpText, PauseOption, Score};
+use crate::*;
+
+#[derive(States, Default, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum GameState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+    Settings, // Opened from the main menu
+    GameOver,
+    GameWin,
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy, // Wide paddle, slow ball, every block breaks in one hit
+    #[default]
+    Normal,
+    Hard, // Narrow paddle, fast ball, tougher top rows and a single life
+}
+
+impl Difficulty {
+    // Pick the difficulty from a `--difficulty easy|normal|hard` argument, None if it's missing or unknown
+    pub fn from_args(args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut args = args.skip_while(|arg| arg != "--difficulty").skip(1);
+        match args.next().as_deref() {
+            Some("easy") => Some(Difficulty::Easy),
+            Some("normal") => Some(Difficulty::Normal),
+            Some("hard") => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn paddle_size(self, config: &GameConfig) -> f32 {
+        match self {
+            Difficulty::Easy => config.paddle_width * 1.25,
+            Difficulty::Normal => config.paddle_width,
+            Difficulty::Hard => config.paddle_width * 0.75,
+        }
+    }
+
+    // Speed the ball is served at
+    pub fn ball_speed(self, config: &GameConfig) -> f32 {
+        match self {
+            Difficulty::Easy => config.ball_start_speed * 0.75,
+            Difficulty::Normal => config.ball_start_speed,
+            Difficulty::Hard => config.ball_start_speed * 1.25,
+        }
+    }
+
+    // Hits a block in `row` that the layout gives `durability` hits takes on this difficulty
+    pub fn block_durability(self, durability: u32, row: usize) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => durability,
+            Difficulty::Hard if row < 2 => durability.max(2),
+            Difficulty::Hard => durability,
+        }
+    }
+
+    pub fn lives(self) -> u32 {
+        match self {
+            Difficulty::Hard => 1,
+            _ => STARTING_LIVES,
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Resource)]
+pub struct Lives(pub u32); // Remaining balls before the game is over
+
+#[derive(Resource)]
+pub struct RespawnTimer(pub Timer); // Counts down until a lost ball is replaced
+
+#[derive(Event)]
+pub struct BallLostEvent; // The last ball in play fell off the bottom
+
+#[derive(Event)]
+pub struct GameWonEvent; // The last level was cleared
+
+#[derive(Resource, Default)]
+pub struct EscapeHold(pub Option<f32>); // Seconds Escape has been held on the pause screen, None when it isn't
+
+#[derive(Resource)]
+pub struct LevelTransition(pub Timer); // Real time the game stays frozen on the "Level N!" text
+
+// Remove balls that hit the bottom of the screen, lose a life once the last one is gone
+// and end the game when none are left
+#[allow(clippy::too_many_arguments)]
+pub fn game_over(mut commands: Commands,
+                 mut lives: ResMut<Lives>,
+                 mut combo: ResMut<Combo>,
+                 mut next_state: ResMut<NextState<GameState>>,
+                 mut lost_events: EventWriter<BallLostEvent>,
+                 dimensions: Res<GameDimensions>,
+                 config: Res<GameConfig>,
+                 balls: Query<(Entity, &Transform), With<Ball>>) {
+
+    let mut remaining = 0;
+    let mut lost = 0;
+    for (ball_entity, ball_tf) in balls.iter() {
+        if ball_tf.translation.y < -dimensions.height / 2.0 + config.ball_size / 2.0 {
+            commands.entity(ball_entity).despawn(); // Remove the lost ball
+            lost += 1;
+        } else {
+            remaining += 1;
+        }
+    }
+    if lost == 0 || remaining > 0 {
+        return; // Still have a ball in play
+    }
+
+    lives.0 = lives.0.saturating_sub(1); // Only lose one life however many balls dropped together
+    combo.0 = 0;
+    lost_events.write(BallLostEvent);
+    if lives.0 > 0 {
+        commands.insert_resource(RespawnTimer(Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once)));
+        return;
+    }
+
+    next_state.set(GameState::GameOver); // Set game state to GameOver
+}
+
+// Move on to the next level once the field is clear, the game is won after the last one
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn game_win(mut commands: Commands,
+                blocks: Query<&Block, Without<Indestructible>>,
+                leftovers: Query<Entity, Or<(With<Ball>, With<PowerUp>, With<Bullet>, With<Particle>, With<Block>)>>,
+                mut level: ResMut<CurrentLevel>,
+                levels: Res<LevelData>,
+                ball_assets: Res<BallAssets>,
+                dimensions: Res<GameDimensions>,
+                config: Res<GameConfig>,
+                difficulty: Res<Difficulty>,
+                mut time: ResMut<Time<Virtual>>,
+                mut next_state: ResMut<NextState<GameState>>,
+                mut won_events: EventWriter<GameWonEvent>) {
+
+    if !blocks.is_empty() {
+        return;
+    }
+    if level.0 + 1 >= levels.levels.len() {
+        next_state.set(GameState::GameWin); // Set game state to GameWin
+        won_events.write(GameWonEvent);
+        return;
+    }
+
+    // Start the next level from a clean field with the ball back on the paddle, the score carries over.
+    // Indestructible blocks are all that's left of the old layout
+    for entity in leftovers.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<RespawnTimer>();
+    level.0 += 1;
+    commands.run_system_cached(spawn_blocks);
+    spawn_ball(&mut commands, &ball_assets, &dimensions, &config, difficulty.ball_speed(&config));
+
+    // Hold everything still for a moment so the player sees the new level coming
+    commands.insert_resource(LevelTransition(Timer::from_seconds(LEVEL_TRANSITION_DELAY, TimerMode::Once)));
+    time.pause();
+}
+
+// Carry on playing once the level text has been up long enough
+pub fn level_transition(mut commands: Commands,
+                        mut transition: ResMut<LevelTransition>,
+                        mut time: ResMut<Time<Virtual>>,
+                        real_time: Res<Time<Real>>, // Virtual time is paused
+                        text: Query<Entity, With<LevelUpText>>) {
+
+    if !transition.0.tick(real_time.delta()).finished() {
+        return;
+    }
+    commands.remove_resource::<LevelTransition>();
+    time.unpause();
+    for entity in text.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn pause_game(mut time: ResMut<Time<Virtual>>,
+                  state: Res<State<GameState>>,
+                  mut next_state: ResMut<NextState<GameState>>,
+                  keyboard_input: Res<ButtonInput<KeyCode>>,
+                  bindings: Res<KeyBindings>,
+                  gamepads: Query<&Gamepad>,
+                  stuck: Query<(), With<StuckToPaddle>>) {
+
+    let paused = *state.get() == GameState::Paused;
+    // A pause key that also launches (Space by default) launches the ball while one is waiting on the paddle.
+    // Escape only opens the menu, escape_key deals with it on the pause screen
+    let launches = bindings.pause == KeyCode::Space || bindings.pause == bindings.serve;
+    if (keyboard_input.just_pressed(bindings.pause) && (stuck.is_empty() || paused || !launches))
+        || (keyboard_input.just_pressed(KeyCode::Escape) && !paused)
+        || gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::Start)) {
+        set_paused(!paused, &mut time, &mut next_state);
+    }
+}
+
+// Pause like the pause key does when the player switches to another window. Getting focus back
+// leaves it paused, the player resumes when they're ready
+pub fn pause_on_focus_lost(mut events: EventReader<WindowFocused>,
+                           mut time: ResMut<Time<Virtual>>,
+                           mut next_state: ResMut<NextState<GameState>>) {
+
+    if events.read().any(|event| !event.focused) {
+        set_paused(true, &mut time, &mut next_state);
+    }
+}
+
+fn set_paused(pause: bool, time: &mut Time<Virtual>, next_state: &mut NextState<GameState>) {
+    if pause {
+        next_state.set(GameState::Paused); // Set game state to Paused
+        time.pause();
+    } else {
+        next_state.set(GameState::Playing); // Set game state to Playing
+        time.unpause();
+    }
+}
+
+// On the pause screen tapping Escape resumes and holding it quits the game
+pub fn escape_key(mut hold: ResMut<EscapeHold>,
+                  mut time: ResMut<Time<Virtual>>,
+                  mut next_state: ResMut<NextState<GameState>>,
+                  mut exit: EventWriter<AppExit>,
+                  real_time: Res<Time<Real>>, // Virtual time is paused
+                  keyboard_input: Res<ButtonInput<KeyCode>>) {
+
+    // Only presses made on the pause screen count, not the one that opened it
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        hold.0 = Some(0.0);
+        return;
+    }
+    let Some(held) = hold.0 else {
+        return;
+    };
+
+    if keyboard_input.just_released(KeyCode::Escape) {
+        hold.0 = None;
+        set_paused(false, &mut time, &mut next_state);
+    } else if held + real_time.delta_secs() >= QUIT_HOLD_TIME {
+        exit.write(AppExit::Success);
+    } else {
+        hold.0 = Some(held + real_time.delta_secs());
+    }
+}
+
+// Pick a pause menu option with Up/Down and Enter, or straight away with the quit key
+// (Space resumes through pause_game)
+pub fn pause_menu(mut commands: Commands,
+                  mut selected: ResMut<PauseOption>,
+                  mut time: ResMut<Time<Virtual>>,
+                  mut next_state: ResMut<NextState<GameState>>,
+                  keyboard_input: Res<ButtonInput<KeyCode>>,
+                  bindings: Res<KeyBindings>,
+                  game_entities: Query<Entity, With<DespawnOnGameOver>>) {
+
+    if keyboard_input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        *selected = PauseOption::Resume;
+    } else if keyboard_input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        *selected = PauseOption::Quit;
+    }
+
+    let choice = if keyboard_input.just_pressed(bindings.quit) {
+        PauseOption::Quit
+    } else if keyboard_input.just_pressed(KeyCode::Enter) {
+        *selected
+    } else {
+        return;
+    };
+
+    time.unpause();
+    match choice {
+        PauseOption::Resume => next_state.set(GameState::Playing),
+        PauseOption::Quit => {
+            // Leave nothing of this game behind, the menu starts a new one
+            for entity in game_entities.iter() {
+                commands.entity(entity).despawn();
+            }
+            commands.remove_resource::<RespawnTimer>();
+            commands.remove_resource::<LaunchCountdown>();
+            commands.remove_resource::<LaunchAim>();
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+// Only runs on the end screens, M goes back to the menu and Escape quits through AppExit so Bevy shuts down properly
+pub fn state_handler(mut next_state: ResMut<NextState<GameState>>,
+                     mut exit: EventWriter<AppExit>,
+                     keyboard_input: Res<ButtonInput<KeyCode>>) {
+
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        next_state.set(GameState::Menu); // The board is already gone, the menu spawns a new one
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        exit.write(AppExit::Success);
+    }
+}
+
+// Pick a difficulty with 1, 2 or 3, start the game with Enter, open the settings with S, leave with Escape
+pub fn menu_system(mut commands: Commands,
+                   mut next_state: ResMut<NextState<GameState>>,
+                   mut lives: ResMut<Lives>,
+                   mut settings: ResMut<Settings>,
+                   mut exit: EventWriter<AppExit>,
+                   keyboard_input: Res<ButtonInput<KeyCode>>) {
+
+    if keyboard_input.just_pressed(KeyCode::Digit1) {
+        settings.difficulty = Difficulty::Easy;
+    } else if keyboard_input.just_pressed(KeyCode::Digit2) {
+        settings.difficulty = Difficulty::Normal;
+    } else if keyboard_input.just_pressed(KeyCode::Digit3) {
+        settings.difficulty = Difficulty::Hard;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        new_game(&mut commands, &mut lives, settings.difficulty);
+        next_state.set(GameState::Playing);
+    } else if keyboard_input.just_pressed(KeyCode::KeyS) {
+        next_state.set(GameState::Settings);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        exit.write(AppExit::Success);
+    }
+}
+
+// Start a fresh game from the game over or win screen with the restart key or the gamepad's South button
+pub fn restart_game(mut commands: Commands,
+                    mut next_state: ResMut<NextState<GameState>>,
+                    mut lives: ResMut<Lives>,
+                    difficulty: Res<Difficulty>,
+                    keyboard_input: Res<ButtonInput<KeyCode>>,
+                    bindings: Res<KeyBindings>,
+                    gamepads: Query<&Gamepad>) {
+
+    if !keyboard_input.just_pressed(bindings.restart)
+        && !gamepads.iter().any(|gamepad| gamepad.just_pressed(GamepadButton::South)) {
+        return;
+    }
+
+    // The old board was cleared when the game ended, and the end screen text goes on exit
+    new_game(&mut commands, &mut lives, *difficulty);
+    next_state.set(GameState::Playing);
+}
+
+// Spawn a new board and reset everything carried over from the last game, this also resets the score
+fn new_game(commands: &mut Commands, lives: &mut Lives, difficulty: Difficulty) {
+    commands.remove_resource::<RespawnTimer>();
+    commands.remove_resource::<LevelTransition>();
+    commands.remove_resource::<LaunchCountdown>();
+    commands.remove_resource::<LaunchAim>();
+    commands.remove_resource::<HitPause>();
+    commands.insert_resource(CurrentLevel(0));
+    commands.insert_resource(Score::default());
+    commands.insert_resource(Combo::default());
+    commands.insert_resource(GameTimer::default());
+    lives.0 = difficulty.lives();
+    commands.run_system_cached(spawn_map);
+    commands.run_system_cached(spawn_blocks);
+}
+
+// Despawn every entity tagged with T
+pub fn despawn_all<T: Component>(entities: Query<Entity, With<T>>,
+                                 mut commands: Commands) {
+
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn reset_escape_hold(mut hold: ResMut<EscapeHold>) {
+    hold.0 = None;
+}
@@ -0,0 +1,422 @@
+use std::fmt::Display;
+use bevy::prelude::*;
+
+use crate::ball::{LaunchAim, LaunchCountdown, StuckToPaddle};
+use crate::blocks::BlockDestroyedEvent;
+use crate::effects::Lifetime;
+use crate::highscore::HighScores;
+use crate::levels::CurrentLevel;
+use crate::settings::{key_label, KeyBindings, Settings};
+use crate::state::{Difficulty, EscapeHold, Lives};
+use crate::*;
+
+#[derive(Resource, Default)]
+pub struct Score(pub u32); // Represents the player's score
+
+#[derive(Component)]
+pub struct ScoreText; // Shows the Score resource
+
+#[derive(Resource, Default)]
+pub struct GameTimer(pub f32); // Seconds of play this game, not counting pauses, menus or serve countdowns
+
+#[derive(Component)]
+pub struct TimerText;
+
+#[derive(Resource, Default)]
+pub struct Combo(pub u32); // Blocks broken since the ball last touched the paddle or was lost
+
+#[derive(Component)]
+pub struct ComboText;
+
+#[derive(Component)]
+pub struct ScorePopup; // Floats up from a broken block showing the points it scored
+
+#[derive(Component)]
+pub struct MenuText;
+
+#[derive(Component)]
+pub struct PauseText; // Root of the pause menu, everything in it goes when it closes
+
+#[derive(Component)]
+pub struct Hud; // Root UI node of the in game text
+
+#[derive(Component)]
+pub struct QuitProgressText; // Fills up while Escape is held on the pause screen
+
+#[derive(Component, Resource, Clone, Copy, Default, PartialEq)]
+pub enum PauseOption {
+    #[default]
+    Resume, // Space
+    Quit, // Q, back to the main menu
+}
+
+#[derive(Component)]
+pub struct GameOverText; // Root node of the game over message
+
+#[derive(Component)]
+pub struct GameWinText; // Root node of the win message
+
+#[derive(Component)]
+pub struct LivesText;
+
+#[derive(Component)]
+pub struct LevelText;
+
+#[derive(Component)]
+pub struct DifficultyText;
+
+#[derive(Component)]
+pub struct LevelUpText;
+
+#[derive(Component)]
+pub struct CountdownText;
+
+#[derive(Component)]
+pub struct AimIndicator; // Line showing where the served ball will launch
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub fn show_menu_text(mut commands: Commands,
+                      settings: Res<Settings>) {
+
+    commands.spawn((
+        MenuText,
+        Text2d::new(menu_text(settings.difficulty)),
+        TextFont {
+            font_size: 50.0,
+            ..default()
+        },
+    ));
+}
+
+pub fn update_menu_text(settings: Res<Settings>,
+                        mut text: Query<&mut Text2d, With<MenuText>>) {
+
+    if let Ok(mut text) = text.single_mut() {
+        text.0 = menu_text(settings.difficulty);
+    }
+}
+
+fn menu_text(difficulty: Difficulty) -> String {
+    format!("Rust Breakout\nDifficulty: {} (1/2/3)\nPress Enter to Play\nS for Settings\nEsc to Quit", difficulty)
+}
+
+const OVERLAY_DIM: Color = Color::srgba(0.0, 0.0, 0.0, 0.6); // Behind menus so they read over any block colors
+
+// Full screen UI node stacking its children in the middle, it stays centered whatever the window or camera does
+fn centered_overlay() -> Node {
+    Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(10.0),
+        ..default()
+    }
+}
+
+// The whole pause overlay, despawned together on leaving Paused whichever state comes next
+pub fn show_pause_text(mut commands: Commands,
+                       bindings: Res<KeyBindings>) {
+
+    commands.insert_resource(PauseOption::Resume); // The selected option, pick with Enter
+
+    let option_font = TextFont {
+        font_size: 30.0,
+        ..default()
+    };
+    let hint_font = TextFont {
+        font_size: 20.0,
+        ..default()
+    };
+    commands.spawn((
+        PauseText,
+        centered_overlay(),
+        BackgroundColor(OVERLAY_DIM), // UI draws over the game, the node's background under its texts
+        children![
+            (Text::new("Paused"), TextFont {
+                font_size: 50.0,
+                ..default()
+            }),
+            (PauseOption::Resume, Text::new("Resume (Space)"), option_font.clone()),
+            (PauseOption::Quit, Text::new(format!("Quit to Menu ({})", key_label(bindings.quit))), option_font),
+            (Text::new(format!("{}/{} move   {} launch   M mouse control   {}/Esc pause",
+                               key_label(bindings.move_left),
+                               key_label(bindings.move_right),
+                               key_label(bindings.serve),
+                               key_label(bindings.pause))), hint_font.clone()),
+            (QuitProgressText, Text::new(quit_progress(None)), hint_font),
+        ],
+    ));
+}
+
+pub fn update_quit_progress(hold: Res<EscapeHold>,
+                            mut text: Query<&mut Text, With<QuitProgressText>>) {
+
+    if let Ok(mut text) = text.single_mut() {
+        text.0 = quit_progress(hold.0);
+    }
+}
+
+// "Hold Esc to quit", with a bar filling up while it's held
+fn quit_progress(held: Option<f32>) -> String {
+    let Some(held) = held else {
+        return String::from("Hold Esc to quit");
+    };
+    let filled = ((held / QUIT_HOLD_TIME) * 10.0).round() as usize;
+    format!("Quitting [{}{}]", "#".repeat(filled.min(10)), "-".repeat(10 - filled.min(10)))
+}
+
+// Highlight the selected pause menu option
+pub fn update_pause_selection(selected: Res<PauseOption>,
+                              mut options: Query<(&PauseOption, &mut TextColor)>) {
+
+    for (option, mut color) in options.iter_mut() {
+        color.0 = if option == &*selected {
+            Color::srgb(1.0, 0.8, 0.0)
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+pub fn show_game_over_text(mut commands: Commands,
+                           score: Res<Score>,
+                           timer: Res<GameTimer>,
+                           high_scores: Res<HighScores>,
+                           bindings: Res<KeyBindings>) {
+
+    commands.spawn((
+        GameOverText,
+        centered_overlay(),
+        BackgroundColor(OVERLAY_DIM),
+        children![(
+            Text::new(format!("Game Over!\n{}\nTime: {}\nPress {} to restart, M for menu", score_lines(score.0, &high_scores), format_time(timer.0), key_label(bindings.restart))),
+            TextLayout::new_with_justify(JustifyText::Center),
+            TextFont {
+                font_size: 50.0,
+                ..default()
+            },
+        )],
+    ));
+}
+
+pub fn show_game_win_text(mut commands: Commands,
+                          score: Res<Score>,
+                          timer: Res<GameTimer>,
+                          high_scores: Res<HighScores>,
+                          bindings: Res<KeyBindings>) {
+
+    commands.spawn((
+        GameWinText,
+        centered_overlay(),
+        BackgroundColor(OVERLAY_DIM),
+        children![(
+            Text::new(format!("You Win!\n{}\nTime: {}\nPress {} to restart, M for menu", score_lines(score.0, &high_scores), format_time(timer.0), key_label(bindings.restart))),
+            TextLayout::new_with_justify(JustifyText::Center),
+            TextFont {
+                font_size: 50.0,
+                ..default()
+            },
+        )],
+    ));
+}
+
+// Final score next to the best one, read before this game's score is added to the table
+fn score_lines(score: u32, high_scores: &HighScores) -> String {
+    if high_scores.is_record(score) {
+        format!("New Record: {}", score)
+    } else {
+        format!("Your Score: {}  Best: {}", score, high_scores.best())
+    }
+}
+
+// Add the points for every block broken this frame, multiplied by the combo it made
+pub fn update_score(mut commands: Commands,
+                    mut events: EventReader<BlockDestroyedEvent>,
+                    mut combo: ResMut<Combo>,
+                    mut score: ResMut<Score>) {
+
+    for event in events.read() {
+        combo.0 += 1;
+        let multiplier = combo_multiplier(combo.0);
+        score.0 += event.value * multiplier;
+
+        commands.spawn((
+            ScorePopup,
+            DespawnOnGameOver,
+            Lifetime(Timer::from_seconds(POPUP_LIFETIME, TimerMode::Once)),
+            Text2d::new(popup_text(event.value, multiplier)),
+            Transform::from_translation(event.position.with_z(1.0)), // Over the particles
+            TextFont {
+                font_size: if multiplier > 1 { 20.0 } else { 14.0 },
+                ..default()
+            },
+        ));
+    }
+}
+
+// "+1" for a plain block, "+4 x2!" when a combo multiplied it
+pub fn popup_text(value: u32, multiplier: u32) -> String {
+    if multiplier > 1 {
+        format!("+{} x{}!", value * multiplier, multiplier)
+    } else {
+        format!("+{}", value)
+    }
+}
+
+// Points are doubled every COMBO_STEP blocks in a row: x1, then x2 from the third block, x4 from the sixth...
+pub fn combo_multiplier(combo: u32) -> u32 {
+    (1u32 << (combo / COMBO_STEP).min(31)).min(MAX_COMBO_MULTIPLIER)
+}
+
+// Float score popups upwards and fade them out
+pub fn popup_update(mut commands: Commands,
+                    mut popups: Query<(Entity, &mut Transform, &mut TextColor, &mut Lifetime), With<ScorePopup>>,
+                    time: Res<Time>) {
+
+    for (entity, mut transform, mut color, mut lifetime) in popups.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += POPUP_RISE_SPEED * time.delta_secs();
+        color.0.set_alpha(lifetime.0.fraction_remaining());
+    }
+}
+
+pub fn update_score_display(score: Res<Score>,
+                            mut text: Query<&mut Text, With<ScoreText>>) {
+
+    for mut text in text.iter_mut() {
+        text.0 = format!("Score: {}", *score);
+    }
+}
+
+pub fn update_combo_text(combo: Res<Combo>,
+                         mut text: Query<&mut Text, With<ComboText>>) {
+
+    if let Ok(mut text) = text.single_mut() {
+        text.0 = if combo.0 > 1 {
+            format!("Combo: {} (x{})", combo.0, combo_multiplier(combo.0))
+        } else {
+            String::new() // Nothing to show for a single block
+        };
+    }
+}
+
+pub fn update_lives_text(lives: Res<Lives>,
+                         mut text: Query<&mut Text, With<LivesText>>) {
+
+    if let Ok(mut text) = text.single_mut() {
+        text.0 = format!("Lives: {}", lives.0); // Update the lives text
+    }
+}
+
+// Virtual time stops while paused and between levels, the schedule keeps it from running anywhere else
+pub fn tick_game_timer(mut timer: ResMut<GameTimer>,
+                       time: Res<Time<Virtual>>) {
+
+    timer.0 += time.delta_secs();
+}
+
+pub fn update_timer_text(timer: Res<GameTimer>,
+                         mut text: Query<&mut Text, With<TimerText>>) {
+
+    let time = format!("Time: {}", format_time(timer.0));
+    if let Ok(mut text) = text.single_mut()
+        && text.0 != time {
+        text.0 = time; // Only touched once a second
+    }
+}
+
+// Minutes and seconds, minutes go past 59 rather than adding hours
+pub fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn update_level_text(level: Res<CurrentLevel>,
+                         mut text: Query<&mut Text, With<LevelText>>) {
+
+    if let Ok(mut text) = text.single_mut() {
+        text.0 = format!("Level: {}", level.0 + 1); // Levels are shown counting from 1
+    }
+}
+
+// Announce the level that's about to start
+pub fn show_level_up_text(mut commands: Commands,
+                          level: Res<CurrentLevel>) {
+
+    commands.spawn((
+        LevelUpText,
+        DespawnOnGameOver,
+        Text2d::new(format!("Level {}!", level.0 + 1)),
+        TextFont {
+            font_size: 50.0,
+            ..default()
+        },
+    ));
+}
+
+// Count "3, 2, 1" down over the served ball, gone once it launches
+pub fn update_countdown_text(mut commands: Commands,
+                             countdown: Option<Res<LaunchCountdown>>,
+                             mut text: Query<(Entity, &mut Text2d), With<CountdownText>>) {
+
+    let Some(countdown) = countdown else {
+        for (entity, _) in text.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let seconds = countdown.0.remaining_secs().ceil().max(1.0);
+    if let Ok((_, mut text)) = text.single_mut() {
+        text.0 = format!("{}", seconds);
+    } else {
+        commands.spawn((
+            CountdownText,
+            DespawnOnGameOver,
+            Text2d::new(format!("{}", seconds)),
+            Transform::from_xyz(0.0, -50.0, 0.0), // Below the blocks, above the paddle
+            TextFont {
+                font_size: 50.0,
+                ..default()
+            },
+        ));
+    }
+}
+
+// Point a line from the served ball along its aim, gone once it launches
+pub fn update_aim_indicator(mut commands: Commands,
+                            aim: Option<Res<LaunchAim>>,
+                            balls: Query<&Transform, (With<StuckToPaddle>, Without<AimIndicator>)>,
+                            mut indicator: Query<(Entity, &mut Transform), With<AimIndicator>>) {
+
+    let (Some(aim), Some(ball)) = (aim, balls.iter().next()) else {
+        for (entity, _) in indicator.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+
+    let direction = Vec2::new(aim.0.sin(), aim.0.cos());
+    let transform = Transform::from_translation((ball.translation.truncate() + direction * AIM_LENGTH / 2.0).extend(0.0))
+        .with_rotation(Quat::from_rotation_z(-aim.0));
+    if let Ok((_, mut indicator_tf)) = indicator.single_mut() {
+        *indicator_tf = transform;
+    } else {
+        commands.spawn((
+            AimIndicator,
+            DespawnOnGameOver,
+            Sprite::from_color(Color::srgba(1.0, 1.0, 1.0, 0.5), Vec2::new(3.0, AIM_LENGTH)),
+            transform,
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+use bevy::math::Vec2;
+use rustout::collision::{aabb_overlap, hit_normal, reflect, sweep_hit};
+
+const HALF: Vec2 = Vec2::splat(10.0);
+
+#[test]
+fn separated_boxes_dont_overlap() {
+    assert!(!aabb_overlap(Vec2::ZERO, HALF, Vec2::new(25.0, 0.0), HALF));
+    assert!(!aabb_overlap(Vec2::ZERO, HALF, Vec2::new(0.0, -25.0), HALF));
+    assert!(!aabb_overlap(Vec2::ZERO, HALF, Vec2::new(15.0, 25.0), HALF)); // Overlapping on x only
+    assert_eq!(hit_normal(Vec2::ZERO, HALF, Vec2::new(25.0, 0.0), HALF), None);
+}
+
+#[test]
+fn touching_edges_and_corners_overlap() {
+    assert!(aabb_overlap(Vec2::ZERO, HALF, Vec2::new(20.0, 0.0), HALF));
+    assert!(aabb_overlap(Vec2::ZERO, HALF, Vec2::new(20.0, 20.0), HALF));
+    assert!(aabb_overlap(Vec2::ZERO, HALF, Vec2::new(5.0, 5.0), HALF));
+}
+
+#[test]
+fn hit_normal_points_out_of_the_face_hit() {
+    // Box a is left of box b, sunk in a little
+    assert_eq!(hit_normal(Vec2::new(-18.0, 0.0), HALF, Vec2::ZERO, HALF), Some(Vec2::new(-1.0, 0.0)));
+    // Above box b
+    assert_eq!(hit_normal(Vec2::new(3.0, 19.0), HALF, Vec2::ZERO, HALF), Some(Vec2::new(0.0, 1.0)));
+    // Right on the bottom right corner, both faces
+    assert_eq!(hit_normal(Vec2::new(19.0, -19.0), HALF, Vec2::ZERO, HALF), Some(Vec2::new(1.0, -1.0)));
+}
+
+#[test]
+fn hit_normal_covers_the_bottom_and_right_faces() {
+    // Below box b
+    assert_eq!(hit_normal(Vec2::new(2.0, -18.0), HALF, Vec2::ZERO, HALF), Some(Vec2::new(0.0, -1.0)));
+    // Right of box b
+    assert_eq!(hit_normal(Vec2::new(17.0, -4.0), HALF, Vec2::ZERO, HALF), Some(Vec2::new(1.0, 0.0)));
+}
+
+#[test]
+fn nearly_equal_overlaps_count_as_a_corner() {
+    // Overlaps of 2.0 and 2.5 are within CORNER_TOLERANCE of each other, so both faces
+    assert_eq!(hit_normal(Vec2::new(-18.0, 17.5), HALF, Vec2::ZERO, HALF), Some(Vec2::new(-1.0, 1.0)));
+    // 2.0 and 3.5 aren't, the smaller overlap wins
+    assert_eq!(hit_normal(Vec2::new(-18.0, 16.5), HALF, Vec2::ZERO, HALF), Some(Vec2::new(-1.0, 0.0)));
+    assert_eq!(hit_normal(Vec2::new(-16.5, 18.0), HALF, Vec2::ZERO, HALF), Some(Vec2::new(0.0, 1.0)));
+}
+
+#[test]
+fn sweep_finds_a_hit_between_the_ends_of_the_path() {
+    // Starts and ends clear of the box on either side, the path goes straight through it
+    let (t, normal) = sweep_hit(Vec2::new(-100.0, 0.0), Vec2::new(100.0, 0.0), HALF, Vec2::ZERO, HALF).unwrap();
+    assert!((t - 0.4).abs() < 0.001);
+    assert_eq!(normal, Vec2::new(-1.0, 0.0));
+
+    assert_eq!(sweep_hit(Vec2::new(-100.0, 50.0), Vec2::new(100.0, 50.0), HALF, Vec2::ZERO, HALF), None);
+}
+
+#[test]
+fn sweep_into_a_corner_hits_both_faces() {
+    let (_, normal) = sweep_hit(Vec2::new(-50.0, -50.0), Vec2::ZERO, HALF, Vec2::ZERO, HALF).unwrap();
+    assert_eq!(normal, Vec2::new(-1.0, -1.0));
+}
+
+#[test]
+fn reflect_only_flips_movement_into_the_face() {
+    assert_eq!(reflect(Vec2::new(3.0, -4.0), Vec2::Y), Vec2::new(3.0, 4.0));
+    assert_eq!(reflect(Vec2::new(3.0, 4.0), Vec2::Y), Vec2::new(3.0, 4.0)); // Already moving away
+    assert_eq!(reflect(Vec2::new(3.0, -4.0), Vec2::new(-1.0, 1.0)), Vec2::new(-3.0, 4.0));
+}
@@ -0,0 +1,42 @@
+use rustout::config::GameConfig;
+use rustout::{BALL_SIZE, CONFIG_FILE, PLAYER_SIZE};
+
+#[test]
+fn missing_fields_keep_their_defaults() {
+    let config = GameConfig::from_ron("(paddle_speed: 450.0)").unwrap();
+    assert_eq!(config.paddle_speed, 450.0);
+    assert_eq!(config.ball_size, BALL_SIZE);
+
+    // The shipped file only has comments in it
+    let shipped = std::fs::read_to_string(CONFIG_FILE).unwrap();
+    assert_eq!(GameConfig::from_ron(&shipped).unwrap(), GameConfig::default());
+    assert!(GameConfig::from_ron("(paddle_speed: \"fast\")").is_err());
+}
+
+#[test]
+fn nonsense_values_fall_back_one_field_at_a_time() {
+    let config = GameConfig {
+        paddle_width: 5000.0, // Wider than the window
+        paddle_speed: 500.0,
+        ball_size: -3.0,
+        block_height: f32::NAN,
+        ..Default::default()
+    };
+    let (config, rejected) = config.validated();
+    assert_eq!(rejected, vec!["paddle_width", "ball_size", "block_height"]);
+    assert_eq!(config.paddle_width, PLAYER_SIZE);
+    assert_eq!(config.paddle_speed, 500.0); // Fine, so it's kept
+    assert_eq!(config.ball_size, BALL_SIZE);
+    assert_eq!(config.block_height, GameConfig::default().block_height);
+
+    let (_, rejected) = GameConfig::default().validated();
+    assert!(rejected.is_empty());
+}
+
+#[test]
+fn explosions_and_moving_blocks_scale_with_the_blocks() {
+    let small = GameConfig::default();
+    let big = GameConfig { block_width: small.block_width * 2.0, ..Default::default() };
+    assert_eq!(big.blast_radius(), small.blast_radius() * 2.0);
+    assert_eq!(big.moving_block_range(), small.moving_block_range() * 2.0);
+}
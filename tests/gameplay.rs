@@ -0,0 +1,1471 @@
+use std::time::Duration;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::audio::Decodable;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{PrimaryWindow, WindowFocused, WindowMode, WindowResized};
+use rustout::audio::{play_sounds, GameSounds};
+use rustout::ball::{aim_launch, Surface, respawn_ball, speed_fraction, ball_collision, ball_movement, ball_watchdog, interpolate_balls, BallVisual, BallTrail, clamp_angle, paddle_bounce, launch_ball, LaunchAim, LaunchCountdown, Ball, BallBouncedEvent, BallSpeed, BallAssets, PreviousPosition, Spin, StuckToPaddle};
+use rustout::collision::sweep_hit;
+use rustout::highscore::HighScores;
+use rustout::effects::{render_trail, TrailAssets, TrailDot, hit_pause_on_column_clear, hit_pause_update, HitPause, ScreenShake};
+use rustout::paddle::{player_movement, update_paddle_limits, PaddleLimit, PaddleVelocity, PaddleWidth, Player};
+use rustout::config::GameConfig;
+use rustout::settings::{KeyBindings, Settings};
+use rustout::powerup::{powerup_collision, powerup_expiry, PowerUp, PowerUpKind, SlowBall};
+use rustout::laser::{bullet_collision, Bullet};
+use rustout::blocks::{block_collision, BlockGrid, move_blocks, Moving, BlockRow, explode_blocks, spawn_blocks, Explosive, ExplosionEvent, Indestructible, row_points, speed_up, Block, BlockDestroyedEvent, BlockMaterials, Durability, PointValue};
+use rustout::layout::parse_layout;
+use rustout::levels::{CurrentLevel, LevelData};
+use rustout::state::{despawn_all, pause_on_focus_lost, escape_key, game_over, EscapeHold, BallLostEvent, GameWonEvent, game_win, level_transition, pause_menu, state_handler, Difficulty, GameState, LevelTransition, Lives, RespawnTimer};
+use rustout::ui::{combo_multiplier, format_time, popup_text, show_game_over_text, tick_game_timer, GameTimer, update_score, update_score_display, Combo, GameOverText, PauseOption, Score, ScoreText};
+use rustout::{resize_play_area, BALL_TIME_SPEEDUP, BALL_MAX_X_SPEED, HIT_PAUSE, BALL_LOST_SHAKE, SHAKE_DURATION, SHAKE_INTENSITY, toggle_fullscreen, DespawnOnGameOver, MIN_WINDOW_WIDTH, GameDimensions, GameRng, Velocity, AIM_SPEED, BALL_SIZE, BALL_START_SPEED, BLOCK_HEIGHT, BLOCK_WIDTH, COMBO_STEP, LAUNCH_COUNTDOWN, LEVEL_TRANSITION_DELAY, MAX_BALL_SPEED, MAX_COMBO_MULTIPLIER, MAX_LAUNCH_ANGLE, MIN_AXIS_SPEED, MULTIBALL_SPREAD, PADDLE_SPEED, SLOW_BALL_DURATION, SLOW_BALL_FACTOR, QUIT_HOLD_TIME, STALL_TIMEOUT, TRAIL_LENGTH, WINDOW_HEIGHT, WINDOW_WIDTH};
+
+const STEP: Duration = Duration::from_millis(100);
+
+// App that advances time by a fixed step every update
+fn test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(STEP))
+        .init_resource::<GameDimensions>()
+        .init_resource::<GameConfig>()
+        .init_resource::<KeyBindings>(); // Read by every input system
+    app.update(); // The first update only starts the clock
+    app
+}
+
+// App running block collisions and scoring
+fn block_app() -> App {
+    let mut app = test_app();
+    app.add_event::<BlockDestroyedEvent>()
+        .add_event::<ExplosionEvent>()
+        .add_systems(Update, (block_collision, explode_blocks, update_score).chain())
+        .insert_resource(BlockMaterials(vec![vec![Handle::default()]]))
+        .init_resource::<ScreenShake>()
+        .init_resource::<Score>()
+        .init_resource::<Combo>();
+    app
+}
+
+// App running the ball's whole physics step: movement, then paddle and block collisions
+fn physics_app() -> App {
+    let mut app = block_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, (ball_movement, ball_collision).chain().before(block_collision));
+    app
+}
+
+fn step(app: &mut App, updates: usize) {
+    for _ in 0..updates {
+        app.update();
+    }
+}
+
+fn block_count(app: &mut App) -> usize {
+    app.world_mut().query::<&Block>().iter(app.world()).count()
+}
+
+fn spawn_block(app: &mut App, position: Vec2, points: u32) -> Entity {
+    app.world_mut().spawn((
+        Block,
+        Durability(1),
+        PointValue(points),
+        Transform::from_translation(position.extend(0.0)),
+        MeshMaterial2d::<ColorMaterial>(Handle::default()),
+    )).id()
+}
+
+// Ball that moved from start to end since the last frame
+fn spawn_moved_ball(app: &mut App, start: Vec2, end: Vec2, velocity: Vec2) -> Entity {
+    app.world_mut().spawn((
+        Ball,
+        Transform::from_translation(end.extend(0.0)),
+        PreviousPosition(start),
+        Velocity(velocity),
+    )).id()
+}
+
+// App that removes lost balls and takes lives
+fn lives_app() -> App {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .insert_resource(Lives(3))
+        .insert_resource(Combo(5))
+        .add_event::<BallLostEvent>()
+        .add_systems(Update, game_over);
+    app
+}
+
+#[test]
+fn menu_runs_without_a_board() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .init_resource::<Difficulty>()
+        .insert_resource(RespawnTimer(Timer::from_seconds(0.0, TimerMode::Once))) // Left over from a quit game
+        .add_systems(Update, respawn_ball); // Not gated on the state, like in the game
+    step(&mut app, 3); // Panicked when BallAssets was required
+
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Menu);
+    assert_eq!(app.world_mut().query::<&Ball>().iter(app.world()).count(), 0);
+}
+
+#[test]
+fn ball_moves_by_its_velocity() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let velocity = Vec2::new(100.0, -200.0);
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, 0.0, 0.0),
+        Velocity(velocity),
+        BallSpeed(velocity.length()),
+    )).id();
+
+    app.update();
+
+    let translation = app.world().get::<Transform>(ball).unwrap().translation;
+    assert!((translation.x - 10.0).abs() < 0.01);
+    assert!((translation.y + 20.0).abs() < 0.01);
+    assert_eq!(app.world().get::<PreviousPosition>(ball).unwrap().0, Vec2::ZERO);
+}
+
+#[test]
+fn ball_at_5000_px_per_second_breaks_a_block_it_would_jump_over() {
+    let mut app = physics_app();
+    spawn_block(&mut app, Vec2::ZERO, 1);
+    let velocity = Vec2::new(1.0, 5.0).normalize() * 5000.0;
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(-50.0, -250.0, 0.0),
+        Velocity(velocity),
+        BallSpeed(5000.0),
+    )).id();
+
+    app.update(); // One 100ms step moves it about 500px, over a block 35px high
+
+    assert_eq!(block_count(&mut app), 0);
+    let ball_tf = app.world().get::<Transform>(ball).unwrap();
+    assert!(ball_tf.translation.y < 0.0); // Stopped at the block's bottom face, not past it
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y < 0.0);
+}
+
+#[test]
+fn ball_too_fast_to_land_on_the_paddle_still_bounces_off_it() {
+    let mut app = physics_app();
+    let dimensions = GameDimensions::default();
+    let paddle_y = dimensions.player_y();
+    app.world_mut().spawn((Player, PaddleWidth(200.0), Transform::from_xyz(0.0, paddle_y, 0.0)));
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, paddle_y + 100.0, 0.0),
+        Velocity(Vec2::new(0.0, -3000.0)),
+        BallSpeed(3000.0),
+    )).id();
+
+    app.update(); // One 100ms step ends 200px under the paddle
+
+    assert!(app.world().get::<Transform>(ball).unwrap().translation.y >= paddle_y);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y > 0.0);
+}
+
+#[test]
+fn ball_breaks_a_block_and_comes_back_off_the_paddle() {
+    let mut app = physics_app();
+    let dimensions = GameDimensions::default();
+    app.world_mut().spawn((Player, PaddleWidth(400.0), Transform::from_xyz(0.0, dimensions.player_y(), 0.0)));
+    spawn_block(&mut app, Vec2::new(0.0, 100.0), 1);
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, -200.0, 0.0),
+        PreviousPosition(Vec2::new(0.0, -200.0)),
+        Velocity(Vec2::Y),
+        BallSpeed(BALL_START_SPEED),
+    )).id();
+
+    step(&mut app, 10);
+    assert_eq!(block_count(&mut app), 0);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y < 0.0);
+
+    step(&mut app, 12);
+    let position = app.world().get::<Transform>(ball).unwrap().translation;
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y > 0.0);
+    assert!(position.y > dimensions.player_y()); // Bounced off the paddle instead of falling through
+}
+
+#[test]
+fn moving_paddle_puts_spin_on_the_ball() {
+    let mut app = physics_app();
+    let dimensions = GameDimensions::default();
+    app.world_mut().spawn((
+        Player,
+        PaddleWidth(400.0),
+        PaddleVelocity { x: PADDLE_SPEED * 2.0, last_x: 0.0 }, // Faster than the keys move it, like a flicked mouse
+        Transform::from_xyz(0.0, dimensions.player_y(), 0.0),
+    ));
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, dimensions.player_y() + 30.0, 0.0),
+        PreviousPosition(Vec2::new(0.0, dimensions.player_y() + 30.0)),
+        Velocity(Vec2::NEG_Y),
+        BallSpeed(BALL_START_SPEED),
+    )).id();
+
+    app.update();
+
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y > 0.0);
+    assert_eq!(app.world().get::<Spin>(ball).unwrap().0, 1.0); // Capped
+}
+
+#[test]
+fn spin_curves_the_ball_and_wears_off() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let start = Vec2::new(-100.0, 300.0);
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, -300.0, 0.0),
+        Velocity(start),
+        BallSpeed(start.length()),
+        Spin(1.0),
+    )).id();
+    let plain = app.world_mut().spawn((Ball, Transform::from_xyz(0.0, -300.0, 0.0), Velocity(start), BallSpeed(start.length()))).id();
+
+    app.update();
+    let vel = app.world().get::<Velocity>(ball).unwrap().0;
+    assert!(vel.x > app.world().get::<Velocity>(plain).unwrap().0.x);
+    assert!((vel.length() - start.length()).abs() < 0.01); // Turned, not sped up
+
+    step(&mut app, 9);
+    let spin = app.world().get::<Spin>(ball).unwrap().0;
+    assert!(spin > 0.0 && spin < 0.5);
+}
+
+#[test]
+fn head_on_wall_hits_stop_the_spin() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let x = WINDOW_WIDTH / 2.0 - BALL_SIZE / 2.0 - 5.0;
+    let head_on = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(x, 0.0, 0.0),
+        Velocity(Vec2::new(400.0, 100.0)),
+        BallSpeed(400.0),
+        Spin(0.5),
+    )).id();
+    let glancing = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(x, 0.0, 0.0),
+        Velocity(Vec2::new(100.0, 400.0)),
+        BallSpeed(400.0),
+        Spin(0.5),
+    )).id();
+
+    app.update();
+
+    assert!(app.world().get::<Velocity>(head_on).unwrap().0.x < 0.0);
+    assert_eq!(app.world().get::<Spin>(head_on).unwrap().0, 0.0);
+    assert!(app.world().get::<Velocity>(glancing).unwrap().0.x < 0.0);
+    assert!(app.world().get::<Spin>(glancing).unwrap().0 > 0.0);
+}
+
+#[test]
+fn ball_is_drawn_between_its_fixed_steps() {
+    let mut app = test_app();
+    app.insert_resource(Time::<Fixed>::from_seconds(1.0))
+        .add_systems(Update, interpolate_balls);
+    let ball = app.world_mut().spawn((Ball, Transform::from_xyz(10.0, 0.0, 0.0), PreviousPosition(Vec2::ZERO))).id();
+    let visual = app.world_mut().spawn((BallVisual, Transform::default(), ChildOf(ball))).id();
+
+    app.update(); // A tenth of the way to the next fixed step
+
+    let offset = app.world().get::<Transform>(visual).unwrap().translation;
+    assert!((offset.x + 9.0).abs() < 0.01);
+}
+
+#[test]
+fn trail_keeps_only_the_latest_positions() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let ball = app.world_mut().spawn((Ball, Transform::default(), Velocity(Vec2::new(10.0, 10.0)), BallSpeed(10.0))).id();
+
+    for _ in 0..TRAIL_LENGTH + 5 {
+        app.update();
+    }
+
+    let trail = &app.world().get::<BallTrail>(ball).unwrap().0;
+    assert_eq!(trail.len(), TRAIL_LENGTH);
+    assert_eq!(trail[0], app.world().get::<PreviousPosition>(ball).unwrap().0); // Newest first
+}
+
+#[test]
+fn trail_dots_shrink_with_age_and_stay_put_while_paused() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .insert_resource(TrailAssets { mesh: Handle::default(), materials: vec![Handle::default(); TRAIL_LENGTH] })
+        .add_systems(Update, render_trail.run_if(in_state(GameState::Playing)));
+    let positions = [Vec2::new(0.0, 0.0), Vec2::new(0.0, -10.0), Vec2::new(0.0, -20.0)];
+    let ball = app.world_mut().spawn(BallTrail(positions.into())).id();
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+
+    let dots = |app: &mut App| {
+        let mut dots = app.world_mut().query_filtered::<&Transform, With<TrailDot>>()
+            .iter(app.world())
+            .map(|transform| (transform.translation.truncate(), transform.scale.x))
+            .collect::<Vec<_>>();
+        dots.sort_by(|a, b| b.0.y.total_cmp(&a.0.y)); // Newest first
+        dots
+    };
+    let before = dots(&mut app);
+    assert_eq!(before.len(), 3);
+    assert!(before[0].1 > before[1].1 && before[1].1 > before[2].1);
+
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Paused);
+    app.update();
+    app.world_mut().get_mut::<BallTrail>(ball).unwrap().0.clear();
+    app.update();
+    assert_eq!(dots(&mut app), before); // Frozen, not cleared
+}
+
+#[test]
+fn block_breaks_and_scores_when_hit() {
+    let mut app = block_app();
+    let block = spawn_block(&mut app, Vec2::new(0.0, 100.0), 3);
+    // Moved straight up through the block
+    let ball = spawn_moved_ball(&mut app, Vec2::ZERO, Vec2::new(0.0, 150.0), Vec2::new(0.0, 400.0));
+
+    app.update();
+
+    assert!(app.world().get_entity(block).is_err());
+    assert_eq!(app.world().resource::<Score>().0, 3);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y < 0.0);
+}
+
+#[test]
+fn explosions_chain_and_score_each_block_once() {
+    let mut app = block_app();
+    let spacing = BLOCK_WIDTH + 15.0; // Same gap as the level grid
+    let first = spawn_block(&mut app, Vec2::new(0.0, 100.0), 1);
+    let second = spawn_block(&mut app, Vec2::new(spacing, 100.0), 1);
+    app.world_mut().entity_mut(first).insert(Explosive);
+    app.world_mut().entity_mut(second).insert(Explosive);
+    // In reach of both blasts, only the chained one reaches the last block
+    spawn_block(&mut app, Vec2::new(spacing / 2.0, 100.0 + BLOCK_HEIGHT + 10.0), 1);
+    let chained = spawn_block(&mut app, Vec2::new(spacing * 2.0, 100.0), 1);
+    let far = spawn_block(&mut app, Vec2::new(spacing * 2.0 + GameConfig::default().blast_radius() + 1.0, 100.0), 1);
+    spawn_moved_ball(&mut app, Vec2::ZERO, Vec2::new(0.0, 150.0), Vec2::new(0.0, 400.0));
+
+    step(&mut app, 3);
+
+    assert!(app.world().get_entity(chained).is_err());
+    assert!(app.world().get_entity(far).is_ok());
+    assert_eq!(block_count(&mut app), 1);
+    assert_eq!(app.world().resource::<Score>().0, 1 + 1 + 2 + 2); // Four blocks, the combo doubles from the third
+}
+
+#[test]
+fn moving_block_turns_at_its_range_and_the_wall() {
+    let mut app = test_app();
+    app.add_systems(Update, move_blocks);
+    let wall = WINDOW_WIDTH / 2.0 - BLOCK_WIDTH / 2.0;
+    let middle = app.world_mut().spawn((Block, Moving { speed: 300.0, range: 20.0, origin_x: 0.0, step: 0.0 }, Transform::default())).id();
+    let edge = app.world_mut().spawn((Block, Moving { speed: 300.0, range: 100.0, origin_x: wall - 10.0, step: 0.0 }, Transform::from_xyz(wall - 10.0, 0.0, 0.0))).id();
+
+    let mut turned = false;
+    for _ in 0..60 {
+        app.update();
+        let x = app.world().get::<Transform>(middle).unwrap().translation.x;
+        assert!(x.abs() <= 20.0);
+        assert!(app.world().get::<Transform>(edge).unwrap().translation.x <= wall); // Never into the wall
+        turned |= app.world().get::<Moving>(middle).unwrap().speed < 0.0;
+    }
+    assert!(turned);
+}
+
+#[test]
+fn moving_block_cant_jump_over_the_ball() {
+    let mut app = block_app();
+    let block = spawn_block(&mut app, Vec2::new(0.0, 100.0), 1);
+    // Slid right from -200 this step, past a ball sitting at -100
+    app.world_mut().entity_mut(block).insert(Moving { speed: 400.0, range: 200.0, origin_x: 0.0, step: 200.0 });
+    let ball = spawn_moved_ball(&mut app, Vec2::new(-100.0, 100.0), Vec2::new(-100.0, 100.0), Vec2::new(0.0, 400.0));
+
+    app.update();
+
+    assert!(app.world().get_entity(block).is_err());
+    let translation = app.world().get::<Transform>(ball).unwrap().translation;
+    assert!(translation.x >= BLOCK_WIDTH / 2.0 + BALL_SIZE / 2.0 - 0.01); // Put back on the side it was hit from
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.x > 0.0); // Knocked along with the block
+}
+
+#[test]
+fn every_block_broken_in_one_frame_sends_an_event() {
+    let mut app = block_app();
+    spawn_block(&mut app, Vec2::new(-300.0, 100.0), 1);
+    spawn_block(&mut app, Vec2::new(300.0, 100.0), 1);
+    spawn_moved_ball(&mut app, Vec2::new(-300.0, 0.0), Vec2::new(-300.0, 150.0), Vec2::new(0.0, 400.0));
+    spawn_moved_ball(&mut app, Vec2::new(300.0, 0.0), Vec2::new(300.0, 150.0), Vec2::new(0.0, 400.0));
+
+    app.update();
+
+    let events = app.world().resource::<Events<BlockDestroyedEvent>>();
+    assert_eq!(events.get_cursor().read(events).count(), 2);
+    // Both are worth a point at x1, the multiplier only doubles from the COMBO_STEP-th block in a row
+    assert_eq!(app.world().resource::<Score>().0, 2);
+    assert_eq!(app.world().resource::<Combo>().0, 2);
+}
+
+#[test]
+fn balls_bigger_than_a_block_still_find_blocks_cells_away() {
+    let mut app = block_app();
+    app.insert_resource(GameConfig { ball_size: 200.0, ..default() });
+    // The block's center is four cells above the ball's path, its edge is inside the ball
+    spawn_block(&mut app, Vec2::new(0.0, 100.0), 1);
+    let ball = spawn_moved_ball(&mut app, Vec2::new(0.0, -100.0), Vec2::new(0.0, -10.0), Vec2::new(0.0, 400.0));
+
+    app.update();
+
+    assert_eq!(block_count(&mut app), 0);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y < 0.0);
+}
+
+#[test]
+fn ball_on_the_seam_of_two_blocks_bounces_once() {
+    let mut app = block_app();
+    // Side by side, the ball comes up right under where they meet and overlaps both
+    spawn_block(&mut app, Vec2::new(-BLOCK_WIDTH / 2.0, 100.0), 1);
+    spawn_block(&mut app, Vec2::new(BLOCK_WIDTH / 2.0, 100.0), 1);
+    let ball = spawn_moved_ball(&mut app, Vec2::new(0.0, 50.0), Vec2::new(0.0, 90.0), Vec2::new(0.0, 400.0));
+
+    app.update();
+
+    assert_eq!(app.world().get::<Velocity>(ball).unwrap().0, Vec2::new(0.0, -400.0)); // Two flips would send it on up
+    assert_eq!(block_count(&mut app), 1);
+}
+
+#[test]
+fn ball_moving_right_into_a_left_face_only_turns_back() {
+    let mut app = block_app();
+    spawn_block(&mut app, Vec2::new(100.0, 0.0), 1);
+    let face = 100.0 - BLOCK_WIDTH / 2.0 - BALL_SIZE / 2.0; // Where the ball touches the block's left face
+    let ball = spawn_moved_ball(&mut app, Vec2::new(face - 40.0, 0.0), Vec2::new(face + 10.0, 0.0), Vec2::new(400.0, 0.0));
+
+    app.update();
+
+    assert_eq!(app.world().get::<Velocity>(ball).unwrap().0, Vec2::new(-400.0, 0.0));
+    assert!((app.world().get::<Transform>(ball).unwrap().translation.x - face).abs() < 0.01);
+    assert_eq!(block_count(&mut app), 0);
+}
+
+#[test]
+fn grid_skips_every_block_far_from_the_ball() {
+    let block_size = Vec2::new(BLOCK_WIDTH, BLOCK_HEIGHT);
+    let mut grid = BlockGrid::new(block_size);
+    let mut world = World::new();
+    for row in 0..8 {
+        for column in -5..=5 {
+            grid.insert(world.spawn_empty().id(), Vec2::new(column as f32 * BLOCK_WIDTH, 200.0 - row as f32 * BLOCK_HEIGHT));
+        }
+    }
+
+    // Down by the paddle, nowhere near the blocks
+    assert_eq!(grid.nearby(Vec2::new(0.0, -300.0), Vec2::new(10.0, -290.0), BALL_SIZE).count(), 0);
+    // Right under the bottom row, only the blocks around it
+    let near = grid.nearby(Vec2::new(0.0, 200.0 - 8.0 * BLOCK_HEIGHT), Vec2::new(0.0, 200.0 - 7.5 * BLOCK_HEIGHT), BALL_SIZE).count();
+    assert!(near > 0 && near <= 9, "{near}");
+}
+
+#[test]
+fn grid_finds_the_same_hits_as_checking_every_block() {
+    let mut rng = StdRng::seed_from_u64(51); // Fixed, so a failure can be replayed
+    for _ in 0..50 {
+        let mut app = block_app();
+        // Random level on the block grid, about half the cells filled
+        let mut blocks = Vec::new();
+        for row in 0..8 {
+            for column in -3..=3 {
+                if rng.gen_bool(0.5) {
+                    let position = Vec2::new(column as f32 * (BLOCK_WIDTH + 15.0), 200.0 - row as f32 * (BLOCK_HEIGHT + 10.0));
+                    blocks.push((spawn_block(&mut app, position, 1), position));
+                }
+            }
+        }
+        let start = Vec2::new(rng.gen_range(-450.0..450.0), rng.gen_range(-300.0..300.0));
+        let end = start + Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)) * rng.gen_range(0.0..100.0);
+        let velocity = (end - start) * 10.0;
+        spawn_moved_ball(&mut app, start, end, velocity);
+
+        // The first block along the path, out of all of them
+        let first_hit = blocks.iter()
+            .filter_map(|&(entity, position)| {
+                sweep_hit(start, end, Vec2::splat(BALL_SIZE / 2.0), position, Vec2::new(BLOCK_WIDTH, BLOCK_HEIGHT) / 2.0)
+                    .filter(|(_, normal)| velocity.dot(*normal) < 0.0)
+                    .map(|(t, _)| (t, entity))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        app.update();
+
+        let events = app.world().resource::<Events<BlockDestroyedEvent>>();
+        let hit = events.get_cursor().read(events).next().map(|event| event.entity);
+        match (first_hit, hit) {
+            (None, None) => {}
+            (Some((t, _)), Some(hit)) => {
+                // Blocks touched at the same moment can be picked either way
+                let hit_position = blocks.iter().find(|(entity, _)| *entity == hit).unwrap().1;
+                let (hit_t, _) = sweep_hit(start, end, Vec2::splat(BALL_SIZE / 2.0), hit_position, Vec2::new(BLOCK_WIDTH, BLOCK_HEIGHT) / 2.0).unwrap();
+                assert!((hit_t - t).abs() < 0.0001);
+            }
+            other => panic!("grid and brute force disagree: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn combo_multiplier_doubles_every_few_blocks() {
+    assert_eq!(combo_multiplier(1), 1);
+    assert_eq!(combo_multiplier(COMBO_STEP - 1), 1);
+    assert_eq!(combo_multiplier(COMBO_STEP), 2);
+    assert_eq!(combo_multiplier(COMBO_STEP * 2), 4);
+    assert_eq!(combo_multiplier(1000), MAX_COMBO_MULTIPLIER);
+}
+
+#[test]
+fn popups_show_the_multiplied_points() {
+    assert_eq!(popup_text(1, 1), "+1");
+    assert_eq!(popup_text(3, 2), "+6 x2!");
+}
+
+#[test]
+fn score_text_follows_the_score() {
+    let mut app = test_app();
+    app.init_resource::<Score>()
+        .add_systems(Update, update_score_display.run_if(resource_changed::<Score>));
+    let text = app.world_mut().spawn((ScoreText, Text::new("Score: 0"))).id();
+
+    app.world_mut().resource_mut::<Score>().0 = 12;
+    app.update();
+
+    assert_eq!(app.world().get::<Text>(text).unwrap().0, "Score: 12");
+}
+
+#[test]
+fn combo_multiplies_the_points() {
+    let mut app = block_app();
+    app.insert_resource(Combo(2));
+    spawn_block(&mut app, Vec2::new(0.0, 100.0), 3);
+    spawn_moved_ball(&mut app, Vec2::ZERO, Vec2::new(0.0, 150.0), Vec2::new(0.0, 400.0));
+
+    app.update();
+
+    assert_eq!(app.world().resource::<Score>().0, 6); // Third block in a row, points doubled
+    assert_eq!(app.world().resource::<Combo>().0, 3);
+}
+
+#[test]
+fn multiball_fans_two_copies_out_from_the_ball() {
+    let mut app = test_app();
+    app.insert_resource(BallAssets { mesh: Handle::default(), material: Handle::default() })
+        .add_systems(Update, powerup_collision);
+    app.world_mut().spawn((Player, Transform::default()));
+    app.world_mut().spawn((PowerUp(PowerUpKind::MultiBall), Transform::default()));
+    app.world_mut().spawn((Ball, Transform::from_xyz(0.0, 100.0, 0.0), Velocity(Vec2::new(0.0, 400.0)), BallSpeed(400.0)));
+
+    app.update();
+
+    let mut angles: Vec<f32> = app.world_mut().query::<&Velocity>().iter(app.world())
+        .map(|vel| Vec2::Y.angle_to(vel.0))
+        .collect();
+    angles.sort_by(f32::total_cmp);
+    assert_eq!(angles.len(), 3);
+    assert!((angles[0] + MULTIBALL_SPREAD).abs() < 0.001);
+    assert!(angles[1].abs() < 0.001);
+    assert!((angles[2] - MULTIBALL_SPREAD).abs() < 0.001);
+}
+
+#[test]
+fn slow_ball_wears_off_back_to_the_original_speed() {
+    let mut app = test_app();
+    app.add_systems(Update, powerup_expiry);
+    let ball = app.world_mut().spawn((
+        Ball,
+        BallSpeed(400.0 * SLOW_BALL_FACTOR),
+        SlowBall { timer: Timer::from_seconds(SLOW_BALL_DURATION, TimerMode::Once) },
+    )).id();
+    let steps = (SLOW_BALL_DURATION / STEP.as_secs_f32()).ceil() as usize;
+
+    step(&mut app, steps - 1);
+    assert!(app.world().get::<SlowBall>(ball).is_some());
+    assert_eq!(app.world().get::<BallSpeed>(ball).unwrap().0, 400.0 * SLOW_BALL_FACTOR);
+
+    app.update();
+    assert!(app.world().get::<SlowBall>(ball).is_none());
+    assert!((app.world().get::<BallSpeed>(ball).unwrap().0 - 400.0).abs() < 0.01);
+}
+
+#[test]
+fn multiball_copies_of_a_slowed_ball_speed_back_up_with_it() {
+    let mut app = test_app();
+    app.insert_resource(BallAssets { mesh: Handle::default(), material: Handle::default() })
+        .add_systems(Update, (powerup_collision, powerup_expiry).chain());
+    app.world_mut().spawn((Player, Transform::default()));
+    app.world_mut().spawn((PowerUp(PowerUpKind::SlowBall), Transform::default()));
+    app.world_mut().spawn((Ball, Transform::from_xyz(0.0, 100.0, 0.0), Velocity(Vec2::new(0.0, 400.0)), BallSpeed(400.0)));
+    app.update();
+    app.world_mut().spawn((PowerUp(PowerUpKind::MultiBall), Transform::default()));
+    app.update();
+
+    let slowed = app.world_mut().query::<(&BallSpeed, &SlowBall)>().iter(app.world()).count();
+    assert_eq!(slowed, 3);
+
+    step(&mut app, (SLOW_BALL_DURATION / STEP.as_secs_f32()).ceil() as usize);
+
+    let speeds: Vec<f32> = app.world_mut().query::<&BallSpeed>().iter(app.world()).map(|speed| speed.0).collect();
+    assert_eq!(speeds.len(), 3);
+    assert!(speeds.iter().all(|speed| (speed - 400.0).abs() < 0.01), "{speeds:?}");
+    assert_eq!(app.world_mut().query::<&SlowBall>().iter(app.world()).count(), 0);
+}
+
+#[test]
+fn losing_the_last_life_shows_one_game_over_text_with_the_score() {
+    let mut app = lives_app();
+    app.insert_resource(Lives(1))
+        .insert_resource(Score(42))
+        .insert_resource(GameTimer(83.5))
+        .init_resource::<HighScores>()
+        .add_systems(OnEnter(GameState::GameOver), (show_game_over_text,
+                                                    despawn_all::<DespawnOnGameOver>).chain());
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+    app.world_mut().spawn((Ball, DespawnOnGameOver, Transform::from_xyz(0.0, -WINDOW_HEIGHT, 0.0)));
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::GameOver);
+    // The message is the text under the centered GameOverText node
+    let texts: Vec<String> = app.world_mut().query::<(&Text, &ChildOf)>()
+        .iter(app.world())
+        .filter(|(_, child_of)| app.world().get::<GameOverText>(child_of.parent()).is_some())
+        .map(|(text, _)| text.0.clone())
+        .collect();
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].contains("42"));
+    assert!(texts[0].contains("Time: 01:23"));
+    // Dimmed but not hiding the game behind it
+    let dim = app.world_mut().query_filtered::<&BackgroundColor, With<GameOverText>>().single(app.world()).unwrap().0.alpha();
+    assert!(dim > 0.0 && dim < 1.0);
+}
+
+#[test]
+fn game_timer_only_runs_while_playing() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .init_resource::<GameTimer>()
+        .add_systems(Update, tick_game_timer.run_if(in_state(GameState::Playing).and(not(resource_exists::<LaunchCountdown>))));
+
+    step(&mut app, 10);
+    assert_eq!(app.world().resource::<GameTimer>().0, 0.0); // Still on the menu
+
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.insert_resource(LaunchCountdown(Timer::from_seconds(LAUNCH_COUNTDOWN, TimerMode::Once)));
+    step(&mut app, 10);
+    assert_eq!(app.world().resource::<GameTimer>().0, 0.0); // Waiting on the serve
+
+    app.world_mut().remove_resource::<LaunchCountdown>();
+    step(&mut app, 10);
+    assert!((app.world().resource::<GameTimer>().0 - 10.0 * STEP.as_secs_f32()).abs() < 0.001);
+
+    app.world_mut().resource_mut::<Time<Virtual>>().pause();
+    step(&mut app, 10);
+    assert!((app.world().resource::<GameTimer>().0 - 10.0 * STEP.as_secs_f32()).abs() < 0.001);
+    assert_eq!(format_time(83.5), "01:23");
+}
+
+#[test]
+fn losing_one_of_two_balls_keeps_the_game_going() {
+    let mut app = lives_app();
+    let lost = app.world_mut().spawn((Ball, Transform::from_xyz(0.0, -WINDOW_HEIGHT, 0.0))).id();
+    let kept = app.world_mut().spawn((Ball, Transform::from_xyz(0.0, 0.0, 0.0))).id();
+
+    app.update();
+
+    assert!(app.world().get_entity(lost).is_err());
+    assert!(app.world().get_entity(kept).is_ok());
+    assert_eq!(app.world().resource::<Lives>().0, 3);
+    assert!(!app.world().contains_resource::<RespawnTimer>());
+}
+
+#[test]
+fn losing_two_of_three_balls_keeps_playing() {
+    let mut app = lives_app();
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+    app.world_mut().spawn((Ball, Transform::from_xyz(-100.0, -WINDOW_HEIGHT, 0.0)));
+    app.world_mut().spawn((Ball, Transform::from_xyz(100.0, -WINDOW_HEIGHT, 0.0)));
+    app.world_mut().spawn((Ball, Transform::from_xyz(0.0, 0.0, 0.0)));
+
+    app.update();
+    app.update();
+
+    assert_eq!(app.world_mut().query::<&Ball>().iter(app.world()).count(), 1);
+    assert_eq!(app.world().resource::<Lives>().0, 3);
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Playing);
+}
+
+#[test]
+fn losing_the_last_ball_costs_a_life() {
+    let mut app = lives_app();
+    app.world_mut().spawn((Ball, Transform::from_xyz(-100.0, -WINDOW_HEIGHT, 0.0)));
+    app.world_mut().spawn((Ball, Transform::from_xyz(100.0, -WINDOW_HEIGHT, 0.0)));
+
+    app.update();
+
+    assert_eq!(app.world_mut().query::<&Ball>().iter(app.world()).count(), 0);
+    assert_eq!(app.world().resource::<Lives>().0, 2); // Only one life for both balls
+    assert!(app.world().contains_resource::<RespawnTimer>());
+    assert_eq!(app.world().resource::<Combo>().0, 0);
+}
+
+#[test]
+fn ball_speed_stays_capped_after_many_blocks() {
+    let mut speed = 400.0;
+    for _ in 0..200 {
+        speed = speed_up(speed);
+    }
+
+    assert!(speed <= MAX_BALL_SPEED + 0.01);
+    assert!(speed > MAX_BALL_SPEED - 0.01);
+}
+
+#[test]
+fn bullet_breaks_block_and_scores() {
+    let mut app = test_app();
+    app.add_event::<BlockDestroyedEvent>()
+        .add_event::<ExplosionEvent>()
+        .init_resource::<Score>()
+        .init_resource::<Combo>()
+        .add_systems(Update, (bullet_collision, update_score).chain());
+    let block = spawn_block(&mut app, Vec2::new(0.0, 100.0), 2);
+    let bullet = app.world_mut().spawn((Bullet, Transform::from_xyz(0.0, 100.0 - BLOCK_HEIGHT / 2.0, 0.0))).id();
+
+    app.update();
+
+    assert!(app.world().get_entity(block).is_err());
+    assert!(app.world().get_entity(bullet).is_err());
+    assert_eq!(app.world().resource::<Score>().0, 2);
+}
+
+#[test]
+fn difficulty_comes_from_the_command_line() {
+    let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter();
+
+    assert_eq!(Difficulty::from_args(args(&["rustout", "--difficulty", "hard"])), Some(Difficulty::Hard));
+    assert_eq!(Difficulty::from_args(args(&["rustout", "--difficulty", "easy"])), Some(Difficulty::Easy));
+    assert_eq!(Difficulty::from_args(args(&["rustout"])), None); // The saved setting is used
+    assert_eq!(Difficulty::from_args(args(&["rustout", "--difficulty"])), None);
+}
+
+#[test]
+fn difficulty_sets_speed_paddle_blocks_and_lives() {
+    assert_eq!(Difficulty::Easy.ball_speed(&GameConfig::default()), 300.0);
+    assert_eq!(Difficulty::Hard.ball_speed(&GameConfig::default()), 500.0);
+    assert_eq!(Difficulty::Easy.paddle_size(&GameConfig::default()), 250.0);
+    assert_eq!(Difficulty::Hard.paddle_size(&GameConfig::default()), 150.0);
+    assert_eq!(Difficulty::Easy.block_durability(3, 0), 1);
+    assert_eq!(Difficulty::Hard.block_durability(1, 1), 2); // Top two rows are tougher
+    assert_eq!(Difficulty::Hard.block_durability(1, 2), 1);
+    assert_eq!(Difficulty::Hard.lives(), 1);
+    assert_eq!(Difficulty::Normal.lives(), 3);
+}
+
+#[test]
+fn clearing_the_field_starts_the_next_level() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .init_resource::<Difficulty>()
+        .insert_resource(CurrentLevel(0))
+        .insert_resource(LevelData::built_in())
+        .insert_resource(BallAssets { mesh: Handle::default(), material: Handle::default() })
+        .add_event::<GameWonEvent>()
+        .add_systems(Update, game_win);
+    let old_ball = app.world_mut().spawn((Ball, Transform::from_xyz(0.0, 0.0, 0.0))).id();
+
+    app.update();
+
+    assert_eq!(app.world().resource::<CurrentLevel>().0, 1);
+    assert!(app.world().get_entity(old_ball).is_err());
+    let blocks = LevelData::built_in().levels[1].breakable_blocks();
+    assert_eq!(app.world_mut().query::<&Block>().iter(app.world()).count(), blocks);
+    assert_eq!(app.world_mut().query::<&StuckToPaddle>().iter(app.world()).count(), 1); // Served from the paddle again
+}
+
+#[test]
+fn indestructible_blocks_dont_stop_the_win() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .init_resource::<Difficulty>()
+        .insert_resource(CurrentLevel(0))
+        .insert_resource(LevelData { levels: vec![parse_layout("#1#\n###").unwrap()] }) // The only level
+        .insert_resource(BallAssets { mesh: Handle::default(), material: Handle::default() })
+        .add_event::<GameWonEvent>()
+        .add_systems(Update, game_win);
+    app.world_mut().run_system_cached(spawn_blocks).unwrap();
+    app.update();
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Menu); // The normal block is still up
+
+    let normal = app.world_mut().query_filtered::<Entity, (With<Block>, Without<Indestructible>)>().single(app.world()).unwrap();
+    app.world_mut().despawn(normal);
+    app.update();
+    app.update();
+
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::GameWin);
+    assert_eq!(app.world_mut().query::<&Indestructible>().iter(app.world()).count(), 5); // Never broken
+}
+
+#[test]
+fn blocks_share_one_material_per_row() {
+    let mut app = test_app();
+    app.init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .init_resource::<Difficulty>()
+        .insert_resource(CurrentLevel(0))
+        .insert_resource(LevelData { levels: vec![parse_layout("111\n111\n111").unwrap()] });
+    app.world_mut().run_system_cached(spawn_blocks).unwrap();
+
+    let blocks = app.world_mut().query::<(&BlockRow, &MeshMaterial2d<ColorMaterial>)>()
+        .iter(app.world())
+        .map(|(row, material)| (row.0, material.0.id()))
+        .collect::<Vec<_>>();
+    for (row, material) in &blocks {
+        for (other_row, other_material) in &blocks {
+            assert_eq!(row == other_row, material == other_material);
+        }
+    }
+    assert_eq!(app.world().resource::<Assets<ColorMaterial>>().len(), 3 * 3 + 2); // Three shades per row, plus grey and orange
+}
+
+#[test]
+fn next_level_starts_after_a_short_pause() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<ColorMaterial>>()
+        .init_resource::<Difficulty>()
+        .insert_resource(CurrentLevel(0))
+        .insert_resource(LevelData::built_in())
+        .insert_resource(BallAssets { mesh: Handle::default(), material: Handle::default() })
+        .add_event::<GameWonEvent>()
+        .add_systems(Update, (game_win.run_if(not(resource_exists::<LevelTransition>)),
+                              level_transition.run_if(resource_exists::<LevelTransition>)));
+
+    app.update();
+    assert!(app.world().resource::<Time<Virtual>>().is_paused());
+
+    for _ in 0..(LEVEL_TRANSITION_DELAY / STEP.as_secs_f32()) as usize + 1 {
+        app.update();
+    }
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+    assert!(!app.world().contains_resource::<LevelTransition>());
+    assert_eq!(app.world().resource::<CurrentLevel>().0, 1); // Didn't skip straight past the new level
+}
+
+#[test]
+fn end_screen_goes_back_to_the_menu() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .add_event::<AppExit>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, state_handler);
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::GameOver);
+    app.update();
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyM);
+    app.update();
+    app.update(); // The state changes on the update after it's set
+
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Menu);
+}
+
+#[test]
+fn quitting_from_pause_clears_the_game() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(PauseOption::Quit)
+        .add_systems(Update, pause_menu);
+    let block = app.world_mut().spawn((Block, DespawnOnGameOver)).id();
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Paused);
+    app.update();
+
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Enter);
+    app.update();
+    app.update(); // The state change applies on the next frame
+
+    assert!(app.world().get_entity(block).is_err());
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Menu);
+}
+
+#[test]
+fn ball_far_past_a_wall_is_pushed_back_inside() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let max_x = WINDOW_WIDTH / 2.0 - BALL_SIZE / 2.0;
+    // A 100ms frame at this speed lands the ball 50px past the wall
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(max_x - 10.0, 0.0, 0.0),
+        Velocity(Vec2::new(600.0, 0.0)),
+        BallSpeed(600.0),
+    )).id();
+
+    app.update();
+
+    assert!(app.world().get::<Transform>(ball).unwrap().translation.x <= max_x);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.x < 0.0);
+
+    app.update(); // Must not flip back outwards on the next frame
+
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.x < 0.0);
+}
+
+#[test]
+fn ball_past_the_left_wall_is_pushed_back_inside() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let max_x = WINDOW_WIDTH / 2.0 - BALL_SIZE / 2.0;
+    // Just past the wall and still heading out of the window
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(-max_x - 2.0, 0.0, 0.0),
+        Velocity(Vec2::new(-300.0, 100.0)),
+        BallSpeed(Vec2::new(-300.0, 100.0).length()),
+    )).id();
+
+    app.update();
+
+    assert!(app.world().get::<Transform>(ball).unwrap().translation.x >= -max_x);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.x > 0.0);
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y > 0.0); // Only the sideways speed flips
+
+    app.update();
+
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.x > 0.0);
+}
+
+#[test]
+fn ball_against_the_ceiling_only_bounces_once() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let max_y = WINDOW_HEIGHT / 2.0 - BALL_SIZE / 2.0;
+    // Starts past the ceiling already heading down, as if it bounced last frame
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, max_y + 5.0, 0.0),
+        Velocity(Vec2::new(0.0, -10.0)),
+    )).id();
+
+    for _ in 0..5 {
+        app.update();
+        assert!(app.world().get::<Velocity>(ball).unwrap().0.y < 0.0); // Never flipped back up
+        assert!(app.world().get::<Transform>(ball).unwrap().translation.y <= max_y);
+    }
+    let events = app.world().resource::<Events<BallBouncedEvent>>();
+    assert_eq!(events.get_cursor().read(events).count(), 0);
+}
+
+#[test]
+fn ball_moving_into_the_ceiling_bounces() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    let max_y = WINDOW_HEIGHT / 2.0 - BALL_SIZE / 2.0;
+    let ball = app.world_mut().spawn((
+        Ball,
+        Transform::from_xyz(0.0, max_y, 0.0),
+        Velocity(Vec2::new(0.0, 100.0)),
+    )).id();
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y < 0.0);
+    assert!(app.world().get::<Transform>(ball).unwrap().translation.y < max_y);
+}
+
+#[test]
+fn top_rows_are_worth_more() {
+    assert_eq!(row_points(0, 5), 5);
+    assert_eq!(row_points(4, 5), 1);
+    assert_eq!(row_points(7, 5), 1); // Never worth nothing
+}
+
+#[test]
+fn window_size_comes_from_the_command_line() {
+    let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter();
+
+    let dimensions = GameDimensions::from_args(args(&["rustout", "--width", "1280", "--height", "800"]));
+    assert_eq!(dimensions, GameDimensions { width: 1280.0, height: 800.0 });
+
+    assert_eq!(GameDimensions::from_args(args(&["rustout"])), GameDimensions::default());
+    // Too small for the blocks to fit, or not a number
+    assert_eq!(GameDimensions::from_args(args(&["rustout", "--width", "300", "--height", "tall"])), GameDimensions::default());
+}
+
+#[test]
+fn seed_comes_from_the_command_line() {
+    let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter();
+
+    assert_eq!(GameRng::from_args(args(&["rustout", "--seed", "1234"])).seed, 1234);
+}
+
+// Ball stuck in one band, so the watchdog keeps kicking it at random
+fn stalled_ball_velocity(seed: u64) -> Vec2 {
+    let mut app = test_app();
+    app.insert_resource(GameRng::new(seed))
+        .add_systems(Update, ball_watchdog);
+    let ball = app.world_mut().spawn((Ball, Transform::default(), Velocity(Vec2::new(400.0, 0.0)))).id();
+    step(&mut app, (3.0 * STALL_TIMEOUT / STEP.as_secs_f32()) as usize);
+    app.world().get::<Velocity>(ball).unwrap().0
+}
+
+#[test]
+fn same_seed_plays_out_the_same() {
+    assert_eq!(stalled_ball_velocity(7), stalled_ball_velocity(7));
+    assert_ne!(stalled_ball_velocity(7), stalled_ball_velocity(8));
+}
+
+#[test]
+fn ball_speeds_up_in_play_but_not_while_paused() {
+    let mut app = test_app();
+    app.add_event::<BallBouncedEvent>()
+        .add_systems(Update, ball_movement);
+    // Ball doesn't require a Transform, without one ball_movement wouldn't see it at all
+    let ball = app.world_mut().spawn((Ball, Transform::default(), Velocity(Vec2::new(0.0, -1.0)), BallSpeed(400.0))).id();
+
+    app.update();
+    let speed = app.world().get::<BallSpeed>(ball).unwrap().0;
+    assert!((speed - (400.0 + BALL_TIME_SPEEDUP * STEP.as_secs_f32())).abs() < 0.001);
+    assert!(app.world().get::<Transform>(ball).unwrap().translation.y < 0.0);
+    assert!((app.world().get::<Velocity>(ball).unwrap().0.length() - 400.0).abs() < 0.01); // Moved at the old speed
+
+    app.world_mut().resource_mut::<Time<Virtual>>().pause();
+    app.update();
+    assert_eq!(app.world().get::<BallSpeed>(ball).unwrap().0, speed);
+}
+
+#[test]
+fn steep_and_flat_angles_are_clamped() {
+    let min = 400.0 * MIN_AXIS_SPEED;
+
+    let vertical = clamp_angle(Vec2::new(0.0, -400.0));
+    assert!(vertical.x >= min - 0.01);
+    assert!(vertical.y < 0.0);
+    assert!((vertical.length() - 400.0).abs() < 0.01);
+
+    let flat = clamp_angle(Vec2::new(-400.0, 1.0));
+    assert!(flat.x < 0.0);
+    assert!(flat.y >= min - 0.01);
+    assert!((flat.length() - 400.0).abs() < 0.01);
+
+    let diagonal = Vec2::new(300.0, 200.0);
+    assert!(clamp_angle(diagonal).abs_diff_eq(diagonal, 0.001)); // Already fine, left alone
+    assert_eq!(clamp_angle(Vec2::ZERO), Vec2::ZERO);
+}
+
+#[test]
+fn paddle_bounce_angle_follows_where_the_paddle_was_hit() {
+    let center = paddle_bounce(0.0, 400.0);
+    assert_eq!(center, Vec2::new(0.0, 400.0)); // Straight up
+
+    let left = paddle_bounce(-1.0, 400.0);
+    assert_eq!(left.x, -BALL_MAX_X_SPEED);
+    assert!(left.y > 0.0);
+    assert!((left.length() - 400.0).abs() < 0.01);
+
+    let right = paddle_bounce(1.0, 400.0);
+    assert_eq!(right.x, BALL_MAX_X_SPEED);
+    assert!(right.y > 0.0);
+    assert!((right.length() - 400.0).abs() < 0.01);
+
+    assert_eq!(paddle_bounce(2.0, 400.0), right); // Past the edge counts as the edge
+    assert!(paddle_bounce(1.0, 200.0).y > 0.0); // Too slow for the full sideways speed, still goes up
+}
+
+#[test]
+fn served_ball_waits_for_the_countdown_then_launches() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(LaunchCountdown(Timer::from_seconds(LAUNCH_COUNTDOWN, TimerMode::Once)))
+        .add_systems(Update, launch_ball);
+    app.world_mut().spawn((Player, Transform::default()));
+    let ball = app.world_mut().spawn((Ball, StuckToPaddle { offset: 0.0 })).id();
+
+    app.update();
+    assert!(app.world().get::<StuckToPaddle>(ball).is_some());
+
+    for _ in 0..(LAUNCH_COUNTDOWN / STEP.as_secs_f32()) as usize {
+        app.update();
+    }
+    assert!(app.world().get::<StuckToPaddle>(ball).is_none());
+    assert!(app.world().get::<Velocity>(ball).unwrap().0.y > 0.0);
+    assert!(!app.world().contains_resource::<LaunchCountdown>());
+}
+
+#[test]
+fn served_ball_launches_along_its_aim() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(LaunchCountdown(Timer::from_seconds(LAUNCH_COUNTDOWN, TimerMode::Once)))
+        .insert_resource(LaunchAim(0.5))
+        .add_systems(Update, launch_ball);
+    app.world_mut().spawn((Player, Transform::default()));
+    let ball = app.world_mut().spawn((Ball, StuckToPaddle { offset: 0.0 })).id();
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Space);
+
+    app.update(); // Launching early skips the rest of the countdown
+
+    let vel = app.world().get::<Velocity>(ball).unwrap().0;
+    assert!(app.world().get::<StuckToPaddle>(ball).is_none());
+    assert!((vel.x.atan2(vel.y) - 0.5).abs() < 0.001);
+    assert!((vel.length() - BALL_START_SPEED).abs() < 0.001);
+    assert!(!app.world().contains_resource::<LaunchAim>());
+}
+
+#[test]
+fn aim_stays_in_the_upward_cone() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<LaunchAim>()
+        .add_systems(Update, aim_launch);
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyD);
+
+    for _ in 0..(2.0 * MAX_LAUNCH_ANGLE / AIM_SPEED / STEP.as_secs_f32()) as usize {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<LaunchAim>().0, MAX_LAUNCH_ANGLE);
+}
+
+#[test]
+fn holding_escape_on_the_pause_screen_quits() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .add_event::<AppExit>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<EscapeHold>()
+        .add_systems(Update, escape_key);
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::Escape);
+    app.update();
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().clear(); // Still held, no longer just pressed
+
+    for _ in 0..(QUIT_HOLD_TIME / STEP.as_secs_f32()) as usize + 1 {
+        app.update();
+    }
+
+    let events = app.world().resource::<Events<AppExit>>();
+    assert!(events.get_cursor().read(events).next().is_some());
+}
+
+#[test]
+fn ball_color_follows_speed_between_start_and_max() {
+    assert_eq!(speed_fraction(400.0, 400.0), 0.0);
+    assert_eq!(speed_fraction(MAX_BALL_SPEED, 400.0), 1.0);
+    assert_eq!(speed_fraction(600.0, 400.0), 0.5);
+    assert_eq!(speed_fraction(200.0, 400.0), 0.0); // Slowed below the start speed stays green
+}
+
+#[test]
+fn shrinking_the_window_pulls_paddle_and_ball_inside() {
+    let mut app = test_app();
+    app.add_event::<WindowResized>()
+        .add_systems(Update, resize_play_area);
+    let paddle = app.world_mut().spawn((Player, PaddleWidth(200.0), Transform::from_xyz(400.0, 0.0, 0.0))).id();
+    let ball = app.world_mut().spawn((Ball, Transform::from_xyz(-480.0, 340.0, 0.0))).id();
+
+    app.world_mut().send_event(WindowResized { window: Entity::PLACEHOLDER, width: MIN_WINDOW_WIDTH, height: 500.0 });
+    app.update();
+
+    let dimensions = *app.world().resource::<GameDimensions>();
+    assert_eq!(dimensions.width, MIN_WINDOW_WIDTH);
+    let paddle_tf = app.world().get::<Transform>(paddle).unwrap().translation;
+    assert_eq!(paddle_tf.x, MIN_WINDOW_WIDTH / 2.0 - 100.0); // Edge against the new wall
+    assert_eq!(paddle_tf.y, dimensions.player_y());
+    let ball_tf = app.world().get::<Transform>(ball).unwrap().translation;
+    assert_eq!(ball_tf.x, -(MIN_WINDOW_WIDTH / 2.0 - BALL_SIZE / 2.0));
+    assert_eq!(ball_tf.y, 250.0 - BALL_SIZE / 2.0);
+    assert_eq!(app.world().get::<PreviousPosition>(ball).unwrap().0, ball_tf.truncate());
+}
+
+// Where the paddle ends up after holding a key for a number of frames
+fn paddle_x_after_holding(key: KeyCode, frames: usize) -> f32 {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, player_movement);
+    let paddle = app.world_mut().spawn((Player, PaddleWidth(200.0), Transform::default())).id();
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(key);
+    step(&mut app, frames);
+    app.world().get::<Transform>(paddle).unwrap().translation.x
+}
+
+#[test]
+fn arrow_keys_move_the_paddle_like_a_and_d() {
+    assert_eq!(paddle_x_after_holding(KeyCode::ArrowLeft, 5), paddle_x_after_holding(KeyCode::KeyA, 5));
+    assert_eq!(paddle_x_after_holding(KeyCode::ArrowRight, 5), paddle_x_after_holding(KeyCode::KeyD, 5));
+    assert!(paddle_x_after_holding(KeyCode::ArrowRight, 5) > 0.0);
+
+    // Held long enough to reach the wall, both stop in the same place
+    assert_eq!(paddle_x_after_holding(KeyCode::ArrowLeft, 600), -(WINDOW_WIDTH / 2.0 - 100.0));
+    assert_eq!(paddle_x_after_holding(KeyCode::KeyA, 600), -(WINDOW_WIDTH / 2.0 - 100.0));
+    assert_eq!(paddle_x_after_holding(KeyCode::ArrowRight, 600), WINDOW_WIDTH / 2.0 - 100.0);
+    assert_eq!(paddle_x_after_holding(KeyCode::KeyD, 600), WINDOW_WIDTH / 2.0 - 100.0);
+}
+
+#[test]
+fn paddle_moves_in_proportion_to_the_frame_time() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, player_movement);
+    let paddle = app.world_mut().spawn((Player, PaddleWidth(200.0), Transform::default())).id();
+    app.world_mut().resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::KeyD);
+    let paddle_x = |app: &App| app.world().get::<Transform>(paddle).unwrap().translation.x;
+
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(10)));
+    app.update();
+    let short = paddle_x(&app);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(30)));
+    app.update();
+    let long = paddle_x(&app) - short;
+
+    assert!((short - PADDLE_SPEED * 0.01).abs() < 0.01);
+    assert!((long - short * 3.0).abs() < 0.01); // Three times the frame, three times as far
+}
+
+#[test]
+fn f11_toggles_fullscreen_and_back_to_the_old_size() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, toggle_fullscreen);
+    let window = app.world_mut().spawn((PrimaryWindow, Window {
+        resolution: (1200.0, 800.0).into(),
+        ..default()
+    })).id();
+    let press = |app: &mut App, keys: &[KeyCode]| {
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.reset_all();
+        for key in keys {
+            input.press(*key);
+        }
+        app.update();
+    };
+
+    press(&mut app, &[KeyCode::F11]);
+    assert!(matches!(app.world().get::<Window>(window).unwrap().mode, WindowMode::BorderlessFullscreen(_)));
+
+    // The fullscreen size comes in, then Alt+Enter goes back
+    app.world_mut().get_mut::<Window>(window).unwrap().resolution.set(1920.0, 1080.0);
+    press(&mut app, &[KeyCode::AltLeft, KeyCode::Enter]);
+    let restored = app.world().get::<Window>(window).unwrap();
+    assert_eq!(restored.mode, WindowMode::Windowed);
+    assert_eq!(restored.resolution.size(), Vec2::new(1200.0, 800.0));
+    assert!(!app.world().resource::<ButtonInput<KeyCode>>().just_pressed(KeyCode::Enter)); // Used up, menus won't see it
+}
+
+#[test]
+fn losing_focus_pauses_and_getting_it_back_doesnt_resume() {
+    let mut app = test_app();
+    app.add_plugins(StatesPlugin)
+        .init_state::<GameState>()
+        .add_event::<WindowFocused>()
+        .add_systems(Update, pause_on_focus_lost.run_if(in_state(GameState::Playing)));
+    app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+    app.update();
+
+    app.world_mut().send_event(WindowFocused { window: Entity::PLACEHOLDER, focused: false });
+    app.update();
+    app.update();
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Paused);
+    assert!(app.world().resource::<Time<Virtual>>().is_paused());
+
+    app.world_mut().send_event(WindowFocused { window: Entity::PLACEHOLDER, focused: true });
+    app.update();
+    app.update();
+    assert_eq!(*app.world().resource::<State<GameState>>().get(), GameState::Paused);
+}
+
+#[test]
+fn a_small_shake_doesnt_cut_a_big_one_short() {
+    let mut shake = ScreenShake::default();
+    shake.kick(BALL_LOST_SHAKE);
+    shake.remaining = SHAKE_DURATION / 2.0;
+    shake.start(BALL_START_SPEED); // A block breaking half way through
+
+    assert_eq!(shake.remaining, SHAKE_DURATION);
+    assert_eq!(shake.strength(), BALL_LOST_SHAKE / 2.0); // Carries on from where the big one was
+    assert!(shake.strength() > SHAKE_INTENSITY);
+}
+
+// App freezing the game when a column of blocks is cleared
+fn hit_pause_app(reduce_motion: bool) -> App {
+    let mut app = test_app();
+    app.add_event::<BlockDestroyedEvent>()
+        .insert_resource(Settings { reduce_motion, ..default() })
+        .add_systems(Update, (hit_pause_update.run_if(resource_exists::<HitPause>),
+                              hit_pause_on_column_clear).chain()); // Same order as the game
+    app
+}
+
+fn break_block_at(app: &mut App, x: f32) {
+    app.world_mut().send_event(BlockDestroyedEvent {
+        entity: Entity::PLACEHOLDER,
+        position: Vec3::new(x, 100.0, 0.0),
+        value: 1,
+        material: Handle::default(),
+    });
+    app.update();
+}
+
+#[test]
+fn clearing_a_column_freezes_the_game_briefly() {
+    let mut app = hit_pause_app(false);
+    spawn_block(&mut app, Vec2::new(0.0, 50.0), 1);
+    spawn_block(&mut app, Vec2::new(300.0, 100.0), 1);
+
+    break_block_at(&mut app, 0.0); // Another block is still in this column
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+
+    break_block_at(&mut app, -300.0); // Nothing left over there
+    assert!(app.world().resource::<Time<Virtual>>().is_paused());
+    step(&mut app, (HIT_PAUSE / STEP.as_secs_f32()) as usize + 1); // Real time keeps going
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+    assert!(!app.world().contains_resource::<HitPause>());
+}
+
+#[test]
+fn every_break_is_checked_in_the_frame_it_happened() {
+    let mut app = hit_pause_app(false);
+    let block = spawn_block(&mut app, Vec2::new(0.0, 50.0), 1);
+    spawn_block(&mut app, Vec2::new(300.0, 100.0), 1);
+
+    // The first break clears its column, the second still has a block over it
+    app.world_mut().send_event(BlockDestroyedEvent {
+        entity: Entity::PLACEHOLDER,
+        position: Vec3::new(-300.0, 100.0, 0.0),
+        value: 1,
+        material: Handle::default(),
+    });
+    break_block_at(&mut app, 0.0);
+    assert!(app.world().resource::<Time<Virtual>>().is_paused());
+
+    // That block is gone by the next frame, the old break mustn't be looked at again
+    app.world_mut().despawn(block);
+    app.update();
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+}
+
+#[test]
+fn reduce_motion_skips_the_hit_pause() {
+    let mut app = hit_pause_app(true);
+    spawn_block(&mut app, Vec2::new(300.0, 100.0), 1);
+
+    break_block_at(&mut app, -300.0);
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+}
+
+#[test]
+fn paddle_limits_follow_width_and_the_hide_setting() {
+    let mut app = test_app();
+    app.init_resource::<Settings>()
+        .add_systems(Update, update_paddle_limits);
+    let paddle = app.world_mut().spawn((Player, PaddleWidth(200.0), Transform::default())).id();
+    let right = app.world_mut().spawn((PaddleLimit(1.0), Transform::default(), Visibility::Hidden)).id();
+    app.update();
+
+    assert_eq!(app.world().get::<Transform>(right).unwrap().translation.x, WINDOW_WIDTH / 2.0 - 100.0);
+    assert_eq!(*app.world().get::<Visibility>(right).unwrap(), Visibility::Inherited);
+
+    app.world_mut().get_mut::<PaddleWidth>(paddle).unwrap().0 = 300.0; // Wide power-up
+    app.world_mut().resource_mut::<Settings>().show_paddle_limits = false;
+    app.update();
+    assert_eq!(app.world().get::<Transform>(right).unwrap().translation.x, WINDOW_WIDTH / 2.0 - 150.0);
+    assert_eq!(*app.world().get::<Visibility>(right).unwrap(), Visibility::Hidden);
+}
+
+// Every clip load_sounds asks for is shipped and decodes
+#[test]
+fn sound_clips_are_shipped_and_decode() {
+    for name in ["bounce", "wall", "break", "lose", "win"] {
+        let bytes = std::fs::read(format!("assets/sounds/{name}.ogg")).unwrap_or_else(|err| panic!("{name}: {err}"));
+        let clip = AudioSource { bytes: bytes.into() };
+        assert!(clip.decoder().count() > 0, "{name} is empty");
+    }
+}
+
+// App playing sounds for the events sent to it
+fn sound_app() -> App {
+    let mut app = test_app();
+    app.init_resource::<Settings>()
+        .insert_resource(GameSounds {
+            paddle: Handle::default(),
+            wall: Handle::default(),
+            block_break: Handle::default(),
+            lose: Handle::default(),
+            win: Handle::default(),
+        })
+        .add_event::<BallBouncedEvent>()
+        .add_event::<BlockDestroyedEvent>()
+        .add_event::<BallLostEvent>()
+        .add_event::<GameWonEvent>()
+        .add_systems(Update, play_sounds);
+    app
+}
+
+fn audio_player_count(app: &mut App) -> usize {
+    app.world_mut().query::<&AudioPlayer>().iter(app.world()).count()
+}
+
+#[test]
+fn many_hits_in_one_frame_play_each_sound_once() {
+    let mut app = sound_app();
+    for _ in 0..3 {
+        app.world_mut().send_event(BallBouncedEvent { surface: Surface::Paddle });
+        app.world_mut().send_event(BallBouncedEvent { surface: Surface::Wall });
+        app.world_mut().send_event(BallBouncedEvent { surface: Surface::Ceiling });
+        app.world_mut().send_event(BlockDestroyedEvent {
+            entity: Entity::PLACEHOLDER,
+            position: Vec3::ZERO,
+            value: 1,
+            material: Handle::default(),
+        });
+    }
+    app.update();
+    assert_eq!(audio_player_count(&mut app), 3); // Paddle, wall and break
+
+    app.update();
+    assert_eq!(audio_player_count(&mut app), 3); // Nothing new without new hits
+}
@@ -0,0 +1,65 @@
+use rustout::highscore::{HighScore, HighScores};
+use rustout::state::Difficulty;
+use rustout::MAX_HIGH_SCORES;
+
+fn scores(high_scores: &HighScores) -> Vec<u32> {
+    high_scores.scores.iter().map(|high_score| high_score.score).collect()
+}
+
+#[test]
+fn scores_are_kept_best_first() {
+    let mut high_scores = HighScores::default();
+    for score in [5, 20, 1, 12] {
+        high_scores.insert(score, Difficulty::Normal);
+    }
+
+    assert_eq!(scores(&high_scores), vec![20, 12, 5, 1]);
+    assert_eq!(high_scores.best(), 20);
+}
+
+#[test]
+fn only_the_top_scores_are_kept() {
+    let mut high_scores = HighScores::default();
+    for score in 0..20 {
+        high_scores.insert(score, Difficulty::Normal);
+    }
+
+    assert_eq!(high_scores.scores.len(), MAX_HIGH_SCORES);
+    assert_eq!(scores(&high_scores).last(), Some(&10)); // The lowest scores fell off
+}
+
+#[test]
+fn record_must_beat_the_best_score() {
+    let mut high_scores = HighScores::default();
+    assert!(!high_scores.is_record(0)); // Nothing to beat, but no points either
+    assert!(high_scores.is_record(3));
+
+    high_scores.insert(3, Difficulty::Normal);
+    assert!(!high_scores.is_record(3));
+    assert!(high_scores.is_record(4));
+}
+
+#[test]
+fn scores_survive_a_round_trip() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(7, Difficulty::Easy);
+    high_scores.insert(42, Difficulty::Hard);
+
+    assert_eq!(HighScores::from_json(&high_scores.to_json()), high_scores);
+}
+
+#[test]
+fn corrupt_file_gives_an_empty_table() {
+    assert_eq!(HighScores::from_json("not json {"), HighScores::default());
+    assert_eq!(HighScores::from_json(""), HighScores::default());
+}
+
+#[test]
+fn scores_remember_the_difficulty() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(10, Difficulty::Hard);
+    high_scores.insert(5, Difficulty::Easy);
+
+    assert_eq!(high_scores.scores[0], HighScore { score: 10, difficulty: Difficulty::Hard });
+    assert_eq!(high_scores.scores[1], HighScore { score: 5, difficulty: Difficulty::Easy });
+}
@@ -0,0 +1,82 @@
+use rustout::layout::{parse_layout, Cell, LayoutError};
+use rustout::levels::{LevelData, LEVELS};
+use rustout::LEVELS_DIR;
+
+#[test]
+fn valid_grid_is_parsed_top_row_first() {
+    let layout = parse_layout("3.#\n12.\n").unwrap();
+
+    assert_eq!(layout.rows, vec![
+        vec![Cell::Block(3), Cell::Empty, Cell::Indestructible],
+        vec![Cell::Block(1), Cell::Block(2), Cell::Empty],
+    ]);
+    assert_eq!(layout.breakable_blocks(), 3);
+}
+
+#[test]
+fn explosive_blocks_count_as_breakable() {
+    let layout = parse_layout("#*#\n").unwrap();
+
+    assert_eq!(layout.rows, vec![vec![Cell::Indestructible, Cell::Explosive, Cell::Indestructible]]);
+    assert_eq!(layout.breakable_blocks(), 1);
+}
+
+#[test]
+fn moving_cells_and_rows_are_marked() {
+    let layout = parse_layout("1c.\n12.~\n").unwrap();
+
+    assert_eq!(layout.rows, vec![
+        vec![Cell::Block(1), Cell::Moving(3), Cell::Empty],
+        vec![Cell::Moving(1), Cell::Moving(2), Cell::Empty], // The marker isn't a cell
+    ]);
+    assert_eq!(layout.breakable_blocks(), 4);
+}
+
+#[test]
+fn blank_lines_and_trailing_whitespace_are_ignored() {
+    let layout = parse_layout("\r\n11 \r\n\r\n22\r\n").unwrap();
+
+    assert_eq!(layout.rows.len(), 2);
+}
+
+#[test]
+fn ragged_rows_are_rejected() {
+    assert_eq!(parse_layout("111\n11\n"),
+               Err(LayoutError::RaggedRow { line: 2, expected: 3, found: 2 }));
+}
+
+#[test]
+fn unknown_characters_are_rejected() {
+    assert_eq!(parse_layout("111\n1x1\n"),
+               Err(LayoutError::UnknownCharacter { line: 2, column: 2, character: 'x' }));
+    assert!(parse_layout("4").is_err()); // Only up to 3 hits
+}
+
+#[test]
+fn level_without_breakable_blocks_is_rejected() {
+    assert_eq!(parse_layout("..#\n"), Err(LayoutError::NoBlocks));
+    assert_eq!(parse_layout(""), Err(LayoutError::NoBlocks));
+}
+
+#[test]
+fn built_in_levels_parse() {
+    assert_eq!(LevelData::built_in().levels.len(), LEVELS.len());
+}
+
+#[test]
+fn shipped_level_files_are_the_built_in_levels() {
+    let mut paths: Vec<_> = std::fs::read_dir(LEVELS_DIR).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    let shipped: Vec<_> = paths.iter()
+        .map(|path| parse_layout(&std::fs::read_to_string(path).unwrap()).unwrap())
+        .collect();
+    assert_eq!(shipped, LevelData::built_in().levels); // A new file has to be added to LEVELS too
+
+    // The special blocks turn up in the game, not only when the files are missing
+    let cells: Vec<&Cell> = shipped.iter().flat_map(|level| level.rows.iter().flatten()).collect();
+    assert!(cells.iter().any(|cell| matches!(cell, Cell::Explosive)));
+    assert!(cells.iter().any(|cell| matches!(cell, Cell::Moving(_))));
+}
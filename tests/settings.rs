@@ -0,0 +1,70 @@
+use rustout::paddle::ControlMode;
+use bevy::prelude::*;
+use rustout::settings::{key_label, KeyBindings, Settings};
+use rustout::state::Difficulty;
+
+#[test]
+fn settings_survive_a_save_and_load() {
+    let settings = Settings {
+        music_volume: 0.3,
+        sfx_volume: 0.7,
+        muted: true,
+        difficulty: Difficulty::Hard,
+        control_mode: ControlMode::Mouse,
+        reduce_motion: true,
+        show_paddle_limits: false,
+    };
+    assert_eq!(Settings::from_json(&settings.to_json()), settings);
+}
+
+#[test]
+fn corrupt_or_partial_file_falls_back_to_defaults() {
+    assert_eq!(Settings::from_json("not json"), Settings::default());
+
+    // Only the volume was saved, the rest keep their defaults
+    let settings = Settings::from_json(r#"{ "sfx_volume": 5.0 }"#);
+    assert_eq!(settings.sfx_volume, 1.0); // Clamped
+    assert_eq!(settings.difficulty, Difficulty::Normal);
+}
+
+#[test]
+fn muting_silences_sound_effects() {
+    let mut settings = Settings { sfx_volume: 0.5, ..Default::default() };
+    assert_eq!(settings.effective_sfx_volume(), 0.5);
+    settings.muted = true;
+    assert_eq!(settings.effective_sfx_volume(), 0.0);
+}
+
+#[test]
+fn key_bindings_survive_a_save_and_load() {
+    let bindings = KeyBindings { move_left: KeyCode::KeyQ, move_right: KeyCode::ArrowRight, pause: KeyCode::KeyP, ..Default::default() };
+    assert_eq!(KeyBindings::from_ron(&bindings.to_ron()), Ok(bindings));
+
+    // They have their own file now, the settings don't save them
+    assert!(!Settings::default().to_json().contains("KeyA"));
+}
+
+#[test]
+fn key_bindings_are_saved_by_name() {
+    let ron = KeyBindings::default().to_ron();
+    assert!(ron.contains("\"KeyA\""));
+
+    // A key this version doesn't know is an error, so the loader can fall back to the defaults
+    let broken = ron.replace("\"KeyA\"", "\"NoSuchKey\"");
+    assert!(KeyBindings::from_ron(&broken).is_err());
+    assert_eq!(key_label(KeyCode::KeyZ), "Z");
+    assert_eq!(key_label(KeyCode::Space), "Space");
+}
+
+#[test]
+fn keys_bound_twice_are_reported() {
+    assert!(KeyBindings::default().conflicts().is_empty());
+
+    let bindings = KeyBindings { restart: KeyCode::KeyD, ..Default::default() };
+    assert_eq!(bindings.conflicts(), vec![("Move Right", "Restart", KeyCode::KeyD)]);
+
+    // Actions left out of the file keep their defaults
+    let partial = KeyBindings::from_ron(r#"(move_left: "KeyJ", move_right: "KeyL", pause: "KeyP")"#).unwrap();
+    assert_eq!(partial.move_left, KeyCode::KeyJ);
+    assert_eq!(partial.serve, KeyCode::KeyW);
+}